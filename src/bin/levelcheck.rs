@@ -0,0 +1,195 @@
+// checks a levels file for mistakes.
+// usage: levelcheck [--branches] [path], path defaults to src/levels.txt.
+// `--branches` also lists where each level's solutions split up.
+// exits with 1 if any level has a problem. checks the solver gives up on are skipped, not failed.
+
+use std::process::ExitCode;
+
+use jam_gmtk_2022::*;
+use jam_gmtk_2022::solver;
+
+
+const SOLUTION_CAP: u64 = 100;
+// states each search may look at. open levels can have millions,
+// so a level past this is reported as unchecked instead of hanging.
+const SEARCH_BUDGET: usize = 200_000;
+
+struct Report {
+    name: String,
+    size: String,
+    goals: String,
+    moves: String,
+    solutions: String,
+    problems: Vec<String>,
+    // checks that were skipped. they don't fail the level.
+    skipped: Vec<String>,
+    branches: Vec<solver::Branch>,
+}
+
+fn check(index: usize, level: &Level) -> Report {
+    let mut problems = vec![];
+    let mut skipped = vec![];
+
+    let reached = solver::flood(level, &Dice::new(level.start));
+    let tile = |x: i32, y: i32| (y*level.size.x + x) as usize;
+
+    for goal in level.goals.iter() {
+        if !reached[tile(goal.x, goal.y)] {
+            problems.push(format!("goal at ({}, {}) can't be reached from the start", goal.x, goal.y));
+        }
+    }
+
+    // every floor tile the start can't get to is part of some other region.
+    let mut regions = 0;
+    let mut covered = reached.clone();
+    for y in 0..level.size.y {
+        for x in 0..level.size.x {
+            if level.get(x, y) == ' ' || covered[tile(x, y)] {
+                continue;
+            }
+
+            regions += 1;
+            let region = solver::flood(level, &Dice::new(glam::IVec2::new(x, y)));
+            for (covered, in_region) in covered.iter_mut().zip(region) {
+                *covered |= in_region;
+            }
+        }
+    }
+    if regions > 0 {
+        problems.push(format!("{} floor region(s) not connected to the start", regions));
+    }
+
+    let mut moves = "-".to_string();
+    let mut solutions = "-".to_string();
+    let mut branches = vec![];
+    match solver::solve_within(level, SEARCH_BUDGET) {
+        Ok(Some(solution)) => {
+            moves = solution.len().to_string();
+
+            match solver::analyze(level, SOLUTION_CAP, SEARCH_BUDGET) {
+                Ok(analysis) => {
+                    solutions = analysis.solutions.to_string();
+                    if analysis.solutions >= SOLUTION_CAP {
+                        solutions += "+";
+                    }
+                    branches = analysis.branches;
+                }
+                Err(e) => {
+                    solutions = "?".to_string();
+                    skipped.push(format!("{}, solutions not counted", e));
+                }
+            }
+        }
+        Ok(None) => problems.push("no solution".to_string()),
+        // not solved isn't the same as unsolvable.
+        Err(e) => {
+            moves = "?".to_string();
+            solutions = "?".to_string();
+            skipped.push(format!("{}, solvability not checked", e));
+        }
+    }
+
+    for line in problems.iter_mut().chain(skipped.iter_mut()) {
+        *line = format!("level {}: {}", index, line);
+    }
+
+    Report {
+        name: level.meta.name.clone().unwrap_or_else(|| "-".to_string()),
+        size: format!("{}x{}", level.size.x, level.size.y),
+        goals: level.goals.len().to_string(),
+        moves,
+        solutions,
+        problems,
+        skipped,
+        branches,
+    }
+}
+
+// like `RRD: L (2), U (1)`, the options with their solution counts.
+fn branch_to_string(branch: &solver::Branch) -> String {
+    let prefix = if branch.prefix.is_empty() { "start".to_string() } else { moves_to_string(&branch.prefix) };
+    let options = branch.options.iter()
+        .map(|(side, count)| format!("{} ({})", side.to_char(), count))
+        .collect::<Vec<_>>();
+    format!("{}: {}", prefix, options.join(", "))
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let show_branches = args.iter().any(|arg| arg == "--branches");
+    let path = args.iter().find(|arg| !arg.starts_with("--")).cloned().unwrap_or_else(|| "src/levels.txt".to_string());
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let (levels, file_errors) = parse_each_level(&text);
+
+    let mut failed = !file_errors.is_empty();
+    for error in file_errors.iter() {
+        println!("{}: {}", path, error);
+    }
+
+    println!("{:>5}  {:<20} {:>5} {:>5} {:>5} {:>9}  status", "level", "name", "size", "goals", "moves", "solutions");
+
+    let mut problems = vec![];
+    let mut skipped = vec![];
+    let mut branches = vec![];
+    for (i, level) in levels.iter().enumerate() {
+        let report = match level {
+            Ok(level) => check(i, level),
+            Err(errors) => Report {
+                name: "-".to_string(),
+                size: "-".to_string(),
+                goals: "-".to_string(),
+                moves: "-".to_string(),
+                solutions: "-".to_string(),
+                problems: errors.iter().map(|e| e.to_string()).collect(),
+                skipped: vec![],
+                branches: vec![],
+            },
+        };
+
+        let status = match (report.problems.is_empty(), report.skipped.is_empty()) {
+            (false, _) => "FAIL",
+            (true, false) => "skipped",
+            (true, true) => "ok",
+        };
+        println!("{:>5}  {:<20} {:>5} {:>5} {:>5} {:>9}  {}",
+            i, report.name, report.size, report.goals, report.moves, report.solutions, status);
+
+        problems.extend(report.problems);
+        skipped.extend(report.skipped);
+        branches.extend(report.branches.iter().map(|branch| format!("level {}: {}", i, branch_to_string(branch))));
+    }
+
+    if show_branches && !branches.is_empty() {
+        println!();
+        for branch in branches.iter() {
+            println!("{}", branch);
+        }
+    }
+
+    if !skipped.is_empty() {
+        println!();
+        for line in skipped.iter() {
+            println!("{}", line);
+        }
+    }
+
+    if !problems.is_empty() {
+        failed = true;
+        println!();
+        for problem in problems.iter() {
+            println!("{}", problem);
+        }
+    }
+
+    if failed {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,68 @@
+// makes random levels with one solution, in the levels.txt format.
+// usage: levelgen [count] [--size WxH] [--goals N] [--min-moves N] [--seed N]
+
+use std::process::ExitCode;
+
+use glam::IVec2;
+
+use jam_gmtk_2022::solver;
+use jam_gmtk_2022::generator::{self, Rng, Settings};
+
+
+fn parse_args(args: &[String], count: &mut usize, seed: &mut u64, settings: &mut Settings) -> Result<(), String> {
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+        let number = |value: &str| value.parse::<usize>().map_err(|_| format!("{} isn't a number", value));
+
+        match arg.as_str() {
+            "--size" => {
+                let value = value()?;
+                let (w, h) = value.split_once('x').ok_or_else(|| format!("size {} isn't WxH", value))?;
+                settings.size = IVec2::new(number(w)? as i32, number(h)? as i32);
+            }
+            "--goals"     => settings.goals     = number(value()?)?,
+            "--min-moves" => settings.min_moves = number(value()?)?,
+            "--seed"      => *seed = number(value()?)? as u64,
+            _ => *count = number(arg)?,
+        }
+    }
+
+    if settings.size.x < 1 || settings.size.y < 1 || (settings.size.x * settings.size.y) as usize > solver::MAX_TILES {
+        return Err(format!("size has to be between 1x1 and {} tiles", solver::MAX_TILES));
+    }
+    if settings.goals < 1 {
+        return Err("need at least one goal".to_string());
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    let mut count = 1;
+    let mut seed = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let mut settings = Settings::default();
+    if let Err(e) = parse_args(&args, &mut count, &mut seed, &mut settings) {
+        eprintln!("{}", e);
+        return ExitCode::FAILURE;
+    }
+
+    let mut rng = Rng::new(seed);
+    eprintln!("seed {}", seed);
+
+    for i in 0..count {
+        let Some(level) = generator::generate(&mut rng, &settings) else {
+            eprintln!("gave up after {} attempts", settings.attempts);
+            return ExitCode::FAILURE;
+        };
+
+        if i > 0 {
+            println!();
+        }
+        println!("{}", level);
+    }
+
+    ExitCode::SUCCESS
+}
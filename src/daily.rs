@@ -0,0 +1,99 @@
+use crate::Level;
+use crate::generator::{self, Rng, Settings};
+
+
+// days since 1970-01-01, in utc, so everyone switches puzzles at the same time.
+pub fn today() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86400)
+        .unwrap_or(0)
+}
+
+// yyyy-mm-dd. this is howard hinnant's `civil_from_days`.
+pub fn date(day: u64) -> String {
+    let z = day as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era*146097;
+    let yoe = (doe - doe/1460 + doe/36524 - doe/146096) / 365;
+    let doy = doe - (365*yoe + yoe/4 - yoe/100);
+    let mp = (5*doy + 2) / 153;
+    let d = doy - (153*mp + 2)/5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era*400 + (m <= 2) as i64;
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+// the same level for everyone on the same day.
+// the generator gives up after its attempts, so this tries easier settings,
+// then falls back to a fixed level, instead of searching forever.
+pub fn level(day: u64) -> Level {
+    let tries = [Settings { min_moves: 16, .. Default::default() }, Settings::default()];
+    let mut rng = Rng::new(day);
+    let mut level = tries.iter()
+        .find_map(|settings| generator::generate(&mut rng, settings))
+        .unwrap_or_else(fallback);
+    level.meta.name = Some(format!("daily {}", date(day)));
+    level
+}
+
+fn fallback() -> Level {
+    Level::parse(&["......", ".s..4.", "......"]).expect("the fallback level is valid")
+}
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DailyResult {
+    pub day: u64,
+    pub solved: bool,
+    pub moves: u32,
+    pub seconds: f64,
+}
+
+impl DailyResult {
+    // solving beats not solving, then fewer moves, then less time.
+    pub fn better_than(&self, other: &DailyResult) -> bool {
+        let key = |result: &DailyResult| (result.solved, std::cmp::Reverse(result.moves));
+        match key(self).cmp(&key(other)) {
+            std::cmp::Ordering::Greater => true,
+            std::cmp::Ordering::Less    => false,
+            std::cmp::Ordering::Equal   => self.seconds < other.seconds,
+        }
+    }
+}
+
+// one result per line: `<day> solved|unsolved <moves> <seconds>`.
+// lines that don't parse are skipped.
+pub fn parse_results(text: &str) -> Vec<DailyResult> {
+    text.lines().filter_map(|line| {
+        let mut parts = line.split_whitespace();
+        let day = parts.next()?.parse().ok()?;
+        let solved = match parts.next()? {
+            "solved"   => true,
+            "unsolved" => false,
+            _ => return None,
+        };
+        let moves = parts.next()?.parse().ok()?;
+        let seconds = parts.next()?.parse().ok()?;
+        Some(DailyResult { day, solved, moves, seconds })
+    }).collect()
+}
+
+pub fn write_results(results: &[DailyResult]) -> String {
+    results.iter().map(|result| {
+        let solved = if result.solved { "solved" } else { "unsolved" };
+        format!("{} {} {} {:.2}\n", result.day, solved, result.moves, result.seconds)
+    }).collect()
+}
+
+// keeps the best result per day.
+pub fn record(results: &mut Vec<DailyResult>, result: DailyResult) {
+    match results.iter_mut().find(|old| old.day == result.day) {
+        Some(old) => {
+            if result.better_than(old) {
+                *old = result;
+            }
+        }
+        None => results.push(result),
+    }
+}
@@ -0,0 +1,110 @@
+use glam::IVec2;
+
+use crate::{Level, LevelMeta, LevelParseError};
+
+
+// what the editor paints on. unlike a `Level`, it can be changed tile by tile,
+// and it keeps the start as an `s` tile, so there's always exactly one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Canvas {
+    pub size: IVec2,
+    // carried over to the level, so editing doesn't lose the headers.
+    pub meta: LevelMeta,
+    tiles: Vec<char>,
+}
+
+impl Canvas {
+    pub const TILES: &'static str = " .123456s";
+
+    // empty, with the start in the middle.
+    pub fn new(size: IVec2) -> Canvas {
+        let mut canvas = Canvas { size, meta: LevelMeta::default(), tiles: vec![' '; (size.x * size.y) as usize] };
+        let start = canvas.index(size / 2);
+        canvas.tiles[start] = 's';
+        canvas
+    }
+
+    // the level in the middle of a canvas of at least `size`.
+    // its par is dropped, that's stale as soon as a tile changes.
+    pub fn from_level(level: &Level, size: IVec2) -> Canvas {
+        let size = size.max(level.size);
+        let offset = (size - level.size) / 2;
+
+        let meta = LevelMeta { par: None, ..level.meta.clone() };
+        let mut canvas = Canvas { size, meta, tiles: vec![' '; (size.x * size.y) as usize] };
+        for y in 0..level.size.y {
+            for x in 0..level.size.x {
+                let pos = IVec2::new(x, y);
+                let tile = if pos == level.start { 's' } else { level.get(x, y) };
+                let index = canvas.index(offset + pos);
+                canvas.tiles[index] = tile;
+            }
+        }
+        canvas
+    }
+
+    fn index(&self, pos: IVec2) -> usize {
+        (pos.y*self.size.x + pos.x) as usize
+    }
+
+    pub fn contains(&self, pos: IVec2) -> bool {
+        pos.x >= 0 && pos.x < self.size.x && pos.y >= 0 && pos.y < self.size.y
+    }
+
+    pub fn get(&self, pos: IVec2) -> char {
+        if self.contains(pos) { self.tiles[self.index(pos)] } else { ' ' }
+    }
+
+    pub fn start(&self) -> IVec2 {
+        let index = self.tiles.iter().position(|tile| *tile == 's').unwrap();
+        IVec2::new(index as i32 % self.size.x, index as i32 / self.size.x)
+    }
+
+    // painting `s` moves the start, and leaves floor where it was.
+    // the start can't be painted over, only moved.
+    // returns whether anything changed.
+    pub fn paint(&mut self, pos: IVec2, tile: char) -> bool {
+        if !self.contains(pos) || !Canvas::TILES.contains(tile) || self.get(pos) == tile || self.get(pos) == 's' {
+            return false;
+        }
+
+        if tile == 's' {
+            let start = self.index(self.start());
+            self.tiles[start] = '.';
+        }
+        let index = self.index(pos);
+        self.tiles[index] = tile;
+        true
+    }
+
+    // the used part of the canvas, in the format `Level::parse` reads.
+    pub fn rows(&self) -> Vec<String> {
+        let used = |x: i32, y: i32| self.get(IVec2::new(x, y)) != ' ';
+        let xs = (0..self.size.x).filter(|x| (0..self.size.y).any(|y| used(*x, y))).collect::<Vec<_>>();
+        let ys = (0..self.size.y).filter(|y| (0..self.size.x).any(|x| used(x, *y))).collect::<Vec<_>>();
+        let (x0, x1) = (xs[0], xs[xs.len() - 1]);
+        let (y0, y1) = (ys[0], ys[ys.len() - 1]);
+
+        (y0..=y1).map(|y| {
+            (x0..=x1).map(|x| self.get(IVec2::new(x, y))).collect::<String>().trim_end().to_string()
+        }).collect()
+    }
+
+    // cropped to the used tiles, ready to play or save.
+    pub fn to_level(&self) -> Result<Level, Vec<LevelParseError>> {
+        let rows = self.rows();
+        let rows = rows.iter().map(|row| row.as_str()).collect::<Vec<_>>();
+        let mut level = Level::parse(&rows)?;
+        level.meta = self.meta.clone();
+        Ok(level)
+    }
+
+    // the whole canvas as a level, so it can be drawn like one.
+    pub fn to_uncropped_level(&self) -> Level {
+        let rows = (0..self.size.y).map(|y| {
+            (0..self.size.x).map(|x| self.get(IVec2::new(x, y))).collect::<String>()
+        }).collect::<Vec<_>>();
+        let rows = rows.iter().map(|row| row.as_str()).collect::<Vec<_>>();
+        Level::parse(&rows).expect("canvas tiles are always valid")
+    }
+}
@@ -0,0 +1,148 @@
+use glam::IVec2;
+
+use crate::{Level, Dice, Side, MOVES, solver};
+
+
+// splitmix64. small, and the same on every platform,
+// so a seed always makes the same levels.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+
+pub struct Settings {
+    // levels get cropped, so this is the most they'll take up.
+    pub size: IVec2,
+    pub goals: usize,
+    pub min_moves: usize,
+    // candidates to try before giving up.
+    pub attempts: usize,
+    // states the solver may look at per candidate.
+    pub budget: usize,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            size: IVec2::new(9, 7),
+            goals: 3,
+            min_moves: 12,
+            attempts: 1000,
+            budget: 5_000,
+        }
+    }
+}
+
+// makes random levels until one has exactly one solution,
+// at least `min_moves` long. sets the level's par to that length.
+pub fn generate(rng: &mut Rng, settings: &Settings) -> Option<Level> {
+    for _ in 0..settings.attempts {
+        let Some(mut level) = candidate(rng, settings) else {
+            continue;
+        };
+
+        // counting gives up early on levels with many solutions, so it goes first.
+        // settings too large for the solver, and candidates it gives up on, never make a level.
+        if !solver::analyze(&level, 2, settings.budget).is_ok_and(|analysis| analysis.is_unique()) {
+            continue;
+        }
+        let Ok(Some(solution)) = solver::solve_within(&level, settings.budget) else {
+            continue;
+        };
+        if solution.len() < settings.min_moves {
+            continue;
+        }
+
+        level.meta.par = Some(solution.len() as u32);
+        return Some(level);
+    }
+    None
+}
+
+// rolls the dice on a random walk and carves out the tiles it touches.
+// some of the tiles along the walk become goals for the face the dice had down there,
+// so the walk itself wins. then a few dead ends get added to make it less obvious.
+fn candidate(rng: &mut Rng, settings: &Settings) -> Option<Level> {
+    let size = settings.size;
+    let mut tiles = vec![' '; (size.x * size.y) as usize];
+    let index = |pos: IVec2| (pos.y*size.x + pos.x) as usize;
+    let inside = |pos: IVec2| pos.x >= 0 && pos.x < size.x && pos.y >= 0 && pos.y < size.y;
+
+    let start = IVec2::new(rng.below(size.x as usize) as i32, rng.below(size.y as usize) as i32);
+    let mut dice = Dice::new(start);
+    tiles[index(start)] = '.';
+
+    let length = (size.x * size.y) as usize / 2;
+    let mut walk = vec![];
+    while walk.len() < length {
+        let options = MOVES.iter().copied().filter(|side| {
+            let target = dice.pos + side.unit();
+            inside(target) && tiles[index(target)] == ' '
+        }).collect::<Vec<Side>>();
+        if options.is_empty() {
+            break;
+        }
+
+        let side = options[rng.below(options.len())];
+        let face = dice.get(side);
+        dice.move_thyself(side);
+        tiles[index(dice.pos)] = '.';
+        walk.push((dice.pos, face));
+    }
+
+    if walk.len() < settings.goals.max(settings.min_moves) {
+        return None;
+    }
+
+    // the last tile is always a goal, so the whole walk matters.
+    let mut goals = vec![walk.len() - 1];
+    while goals.len() < settings.goals {
+        let i = rng.below(walk.len() - 1);
+        if !goals.contains(&i) {
+            goals.push(i);
+        }
+    }
+    for i in goals {
+        let (pos, face) = walk[i];
+        tiles[index(pos)] = (b'0' + face) as char;
+    }
+
+    for _ in 0..walk.len() / 4 {
+        let (pos, _) = walk[rng.below(walk.len())];
+        let target = pos + MOVES[rng.below(MOVES.len())].unit();
+        if inside(target) && tiles[index(target)] == ' ' {
+            tiles[index(target)] = '.';
+        }
+    }
+
+    tiles[index(start)] = 's';
+
+    // crop to the tiles that got used.
+    let used = |x: i32, y: i32| tiles[index(IVec2::new(x, y))] != ' ';
+    let xs = (0..size.x).filter(|x| (0..size.y).any(|y| used(*x, y))).collect::<Vec<_>>();
+    let ys = (0..size.y).filter(|y| (0..size.x).any(|x| used(x, *y))).collect::<Vec<_>>();
+    let (x0, x1) = (xs[0], xs[xs.len() - 1]);
+    let (y0, y1) = (ys[0], ys[ys.len() - 1]);
+
+    let rows = (y0..=y1).map(|y| {
+        (x0..=x1).map(|x| tiles[index(IVec2::new(x, y))]).collect::<String>()
+    }).collect::<Vec<_>>();
+    let rows = rows.iter().map(|row| row.as_str()).collect::<Vec<_>>();
+    Level::parse(&rows).ok()
+}
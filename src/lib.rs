@@ -0,0 +1,745 @@
+//! game rules for a tail of dice.
+//! no rendering, no sound, no clock - the game binary is a front end over this.
+
+use glam::IVec2;
+
+pub mod solver;
+pub mod generator;
+pub mod daily;
+pub mod replay;
+pub mod save;
+pub mod editor;
+
+
+#[derive(Clone)]
+pub struct Level {
+    pub start: IVec2,
+    pub size:  IVec2,
+    tiles: Vec<char>,
+    pub goals: Vec<IVec2>,
+    pub meta: LevelMeta,
+}
+
+// optional `key: value` lines above the grid.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LevelMeta {
+    pub name:   Option<String>,
+    pub author: Option<String>,
+    pub par:    Option<u32>,
+    pub hint:   Option<String>,
+}
+
+impl LevelMeta {
+    // a header line is a word, then a colon.
+    // tiles never contain colons, so this can't eat a row.
+    pub fn split_header(line: &str) -> Option<(&str, &str)> {
+        let (key, value) = line.split_once(':')?;
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        Some((key, value.trim()))
+    }
+}
+
+impl Level {
+    // header lines come first, then the rows of tiles.
+    // short rows are padded with void.
+    // lines and columns in errors are relative to the level, starting at 1.
+    // `parse_levels` moves them into the file.
+    pub fn parse(level: &[&str]) -> Result<Level, Vec<LevelParseError>> {
+        let mut meta = LevelMeta::default();
+        let mut start = None;
+        let mut height = 0;
+        let mut tiles = vec![];
+        let mut goals = vec![];
+        let mut errors = vec![];
+
+        let mut error = |line: usize, column: usize, kind| {
+            errors.push(LevelParseError { level: 0, line: line + 1, column: column + 1, kind });
+        };
+
+        let mut first_row = 0;
+        while let Some((key, value)) = level.get(first_row).and_then(|line| LevelMeta::split_header(line)) {
+            match key {
+                "name"   => meta.name   = Some(value.to_string()),
+                "author" => meta.author = Some(value.to_string()),
+                "hint"   => meta.hint   = Some(value.to_string()),
+                "par" => match value.parse() {
+                    Ok(par) => meta.par = Some(par),
+                    Err(_) => {
+                        // the value is trimmed, so its column is found again in the line.
+                        let line = level[first_row];
+                        let after_colon = &line[key.len() + 1..];
+                        let column = line[..line.len() - after_colon.trim_start().len()].chars().count();
+                        error(first_row, column, LevelParseErrorKind::BadPar(value.to_string()))
+                    }
+                },
+                _ => error(first_row, 0, LevelParseErrorKind::UnknownHeader(key.to_string())),
+            }
+            first_row += 1;
+        }
+
+        let rows = &level[first_row..];
+        let width = rows.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+
+        for (i, line) in rows.iter().enumerate() {
+            let y = height;
+            height += 1;
+
+            for (x, mut tile) in line.chars().enumerate() {
+                let pos = IVec2::new(x as i32, y);
+
+                if !" .123456s".contains(tile) {
+                    error(first_row + i, x, LevelParseErrorKind::UnknownTile(tile));
+                    tile = ' ';
+                }
+
+                if tile == 's' {
+                    if start.is_some() {
+                        error(first_row + i, x, LevelParseErrorKind::DuplicateStart);
+                    }
+                    start = Some(pos);
+                    tile = '.';
+                }
+
+                if "123456".contains(tile) {
+                    goals.push(pos);
+                }
+
+                tiles.push(tile);
+            }
+
+            tiles.resize((height as usize)*width, ' ');
+        }
+
+        if start.is_none() {
+            // a level of only headers has no row to point at, so the last header it is.
+            error(first_row.min(level.len().saturating_sub(1)), 0, LevelParseErrorKind::MissingStart);
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let start = start.unwrap();
+        let size  = IVec2::new(width as i32, height);
+        Ok(Level { start, size, tiles, goals, meta })
+    }
+
+    pub fn get(&self, x: i32, y: i32) -> char {
+        if x >= 0 && x < self.size.x && y >= 0 && y < self.size.y {
+            self.tiles[(y*self.size.x + x) as usize]
+        }
+        else {
+            ' '
+        }
+    }
+
+    pub fn to_goal(tile: char) -> Option<u8> {
+        if "123456".contains(tile) {
+            return Some((tile as u8) - b'1' + 1);
+        }
+        None
+    }
+
+    // whether a dice can roll onto `target` with `face` down.
+    // doesn't know about the tail.
+    pub fn can_enter(&self, target: IVec2, face: u8) -> bool {
+        let tile = self.get(target.x, target.y);
+
+        if tile == ' ' {
+            return false;
+        }
+
+        if let Some(count) = Level::to_goal(tile) {
+            if count != face {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    pub fn detect_win(&self, dice: &Dice) -> bool {
+        self.goals.iter().all(|goal| dice.on_tail(*goal) || *goal == dice.pos)
+    }
+
+    // identifies a level by its tiles, so renaming or reordering levels keeps the progress.
+    // fnv-1a, because std's hasher may change between rust versions.
+    pub fn content_hash(&self) -> u64 {
+        let mut hash = 0xcbf29ce484222325u64;
+        for y in 0..self.size.y {
+            let row = (0..self.size.x).map(|x| {
+                if IVec2::new(x, y) == self.start { 's' } else { self.get(x, y) }
+            }).collect::<String>();
+            // padding isn't part of the level.
+            for byte in row.trim_end().bytes().chain(std::iter::once(b'\n')) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+        hash
+    }
+}
+
+// writes the level in the format `parse_levels` reads, without a trailing empty line.
+impl std::fmt::Display for Level {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let rows = (0..self.size.y).map(|y| {
+            (0..self.size.x).map(|x| {
+                if IVec2::new(x, y) == self.start { 's' } else { self.get(x, y) }
+            }).collect::<String>()
+        }).collect::<Vec<_>>();
+
+        // an all void row would end the level early.
+        let markers = rows.iter().any(|row| row.trim_end().is_empty());
+        if markers {
+            writeln!(f, "begin")?;
+        }
+
+        if let Some(name) = &self.meta.name {
+            writeln!(f, "name: {}", name)?;
+        }
+        if let Some(author) = &self.meta.author {
+            writeln!(f, "author: {}", author)?;
+        }
+        if let Some(par) = self.meta.par {
+            writeln!(f, "par: {}", par)?;
+        }
+        if let Some(hint) = &self.meta.hint {
+            writeln!(f, "hint: {}", hint)?;
+        }
+
+        for (i, row) in rows.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", row)?;
+        }
+
+        if markers {
+            write!(f, "\nend")?;
+        }
+        Ok(())
+    }
+}
+
+// levels are separated by empty lines,
+// or wrapped in `begin` and `end` lines, which lets them contain empty rows.
+// returns the errors of all levels, not just the first one.
+pub fn parse_levels(levels: &str) -> Result<Vec<Level>, Vec<LevelParseError>> {
+    let (results, mut errors) = parse_each_level(levels);
+
+    let mut result = vec![];
+    for level in results {
+        match level {
+            Ok(level) => result.push(level),
+            Err(es) => errors.extend(es),
+        }
+    }
+
+    if !errors.is_empty() {
+        errors.sort_by_key(|e| e.line);
+        return Err(errors);
+    }
+    Ok(result)
+}
+
+// the lines of one level in a levels file.
+struct Group<'a> {
+    // of the first row or header.
+    first_line: usize,
+    lines: Vec<&'a str>,
+    // every line that belongs to the level, `begin` and `end` included.
+    span: std::ops::Range<usize>,
+}
+
+// splits a levels file into levels. the errors are the ones that don't belong to any one level.
+fn group_levels(levels: &str) -> (Vec<Group<'_>>, Vec<LevelParseError>) {
+    let mut groups = vec![];
+    let mut errors = vec![];
+
+    let mut first_line = 0;
+    let mut lines = vec![];
+    let mut begin = None;
+    for (i, line) in levels.split('\n').enumerate() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+
+        if let Some(begin_line) = begin {
+            if line.trim_end() == "end" {
+                groups.push(Group { first_line, lines: std::mem::take(&mut lines), span: begin_line..i + 1 });
+                begin = None;
+            }
+            else {
+                lines.push(line);
+            }
+            continue;
+        }
+
+        match line.trim_end() {
+            "" => {
+                if !lines.is_empty() {
+                    let span = first_line..first_line + lines.len();
+                    groups.push(Group { first_line, lines: std::mem::take(&mut lines), span });
+                }
+            }
+
+            "begin" => {
+                if !lines.is_empty() {
+                    let span = first_line..first_line + lines.len();
+                    groups.push(Group { first_line, lines: std::mem::take(&mut lines), span });
+                }
+                begin = Some(i);
+                first_line = i + 1;
+            }
+
+            "end" => {
+                errors.push(LevelParseError {
+                    level: groups.len(), line: i + 1, column: 1,
+                    kind: LevelParseErrorKind::UnexpectedEnd,
+                });
+            }
+
+            _ => {
+                if lines.is_empty() {
+                    first_line = i;
+                }
+                lines.push(line);
+            }
+        }
+    }
+
+    if let Some(begin) = begin {
+        errors.push(LevelParseError {
+            level: groups.len(), line: begin + 1, column: 1,
+            kind: LevelParseErrorKind::MissingEnd,
+        });
+    }
+    else if !lines.is_empty() {
+        let span = first_line..first_line + lines.len();
+        groups.push(Group { first_line, lines, span });
+    }
+
+    (groups, errors)
+}
+
+// like `parse_levels`, but keeps the levels that did parse.
+// the second result holds the errors that don't belong to any one level.
+pub fn parse_each_level(levels: &str) -> (Vec<Result<Level, Vec<LevelParseError>>>, Vec<LevelParseError>) {
+    let (groups, mut errors) = group_levels(levels);
+
+    if groups.is_empty() && errors.is_empty() {
+        errors.push(LevelParseError { level: 0, line: 1, column: 1, kind: LevelParseErrorKind::NoLevels });
+    }
+
+    let results = groups.iter().enumerate().map(|(index, group)| {
+        Level::parse(&group.lines).map_err(|es| es.into_iter().map(|e| LevelParseError {
+            level: index,
+            line: group.first_line + e.line,
+            ..e
+        }).collect())
+    }).collect();
+
+    (results, errors)
+}
+
+// the levels file with level `index` swapped for `level`, and everything else left as it was.
+// `None` if there's no such level.
+pub fn replace_level(levels: &str, index: usize, level: &Level) -> Option<String> {
+    let (groups, _) = group_levels(levels);
+    let span = groups.get(index)?.span.clone();
+
+    let mut lines = levels.split('\n').map(str::to_string).collect::<Vec<_>>();
+    lines.splice(span, level.to_string().lines().map(str::to_string));
+    Some(lines.join("\n"))
+}
+
+// the levels file with `level` added at the end.
+pub fn append_level(levels: &str, level: &Level) -> String {
+    let levels = levels.trim_end();
+    if levels.is_empty() {
+        return format!("{}\n", level);
+    }
+    format!("{}\n\n{}\n", levels, level)
+}
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum LevelParseErrorKind {
+    UnknownTile(char),
+    UnknownHeader(String),
+    BadPar(String),
+    MissingStart,
+    DuplicateStart,
+    NoLevels,
+    UnexpectedEnd,
+    MissingEnd,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelParseError {
+    pub level: usize,
+    pub line: usize,
+    pub column: usize,
+    pub kind: LevelParseErrorKind,
+}
+
+impl std::fmt::Display for LevelParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "level {}, line {}, column {}: ", self.level, self.line, self.column)?;
+        match &self.kind {
+            LevelParseErrorKind::UnknownTile(tile) =>
+                write!(f, "unknown tile {:?}", tile),
+            LevelParseErrorKind::UnknownHeader(key) =>
+                write!(f, "unknown header {:?}", key),
+            LevelParseErrorKind::BadPar(value) =>
+                write!(f, "par {:?} is not a move count", value),
+            LevelParseErrorKind::MissingStart =>
+                write!(f, "no start tile 's'"),
+            LevelParseErrorKind::DuplicateStart =>
+                write!(f, "more than one start tile 's'"),
+            LevelParseErrorKind::NoLevels =>
+                write!(f, "file has no levels"),
+            LevelParseErrorKind::UnexpectedEnd =>
+                write!(f, "'end' without 'begin'"),
+            LevelParseErrorKind::MissingEnd =>
+                write!(f, "'begin' without 'end'"),
+        }
+    }
+}
+
+impl std::error::Error for LevelParseError {}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(usize)]
+pub enum Side {
+    Floor = 0,
+    Sky   = 1,
+    Left  = 2,
+    Right = 3,
+    Down  = 4,
+    Up    = 5,
+}
+
+impl Side {
+    pub fn unit(self) -> IVec2 {
+        match self {
+            Side::Left  => IVec2::new(-1, 0),
+            Side::Right => IVec2::new( 1, 0),
+            Side::Down  => IVec2::new(0,  1),
+            Side::Up    => IVec2::new(0, -1),
+            _ => unreachable!()
+        }
+    }
+
+    pub fn from_unit(unit: IVec2) -> Side {
+        match (unit.x, unit.y) {
+            (-1, 0) => Side::Left,
+            ( 1, 0) => Side::Right,
+            (0,  1) => Side::Down,
+            (0, -1) => Side::Up,
+            _ => unreachable!()
+        }
+    }
+
+    // move strings, like `RRDLU`.
+    pub fn to_char(self) -> char {
+        match self {
+            Side::Left  => 'L',
+            Side::Right => 'R',
+            Side::Down  => 'D',
+            Side::Up    => 'U',
+            _ => unreachable!()
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<Side> {
+        match c {
+            'L' => Some(Side::Left),
+            'R' => Some(Side::Right),
+            'D' => Some(Side::Down),
+            'U' => Some(Side::Up),
+            _ => None
+        }
+    }
+}
+
+pub const MOVES: [Side; 4] = [Side::Left, Side::Right, Side::Down, Side::Up];
+
+pub fn moves_to_string(moves: &[Side]) -> String {
+    moves.iter().map(|side| side.to_char()).collect()
+}
+
+pub fn parse_moves(moves: &str) -> Option<Vec<Side>> {
+    moves.chars().filter(|c| !c.is_whitespace()).map(Side::from_char).collect()
+}
+
+
+// one of the 24 ways a dice can lie.
+// rolling is a table lookup, so this is cheap to copy, hash and roll.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Orientation(u8);
+
+impl Orientation {
+    pub const START: Orientation = Orientation(0);
+    pub const COUNT: usize = 24;
+
+    pub fn from_index(index: u8) -> Orientation {
+        assert!((index as usize) < Self::COUNT);
+        Orientation(index)
+    }
+
+    pub fn index(self) -> u8 {
+        self.0
+    }
+
+    pub fn get(self, side: Side) -> u8 {
+        ORIENTATIONS.0[self.0 as usize][side as usize]
+    }
+
+    pub fn roll(self, side: Side) -> Orientation {
+        Orientation(ORIENTATIONS.1[self.0 as usize][side as usize - Side::Left as usize])
+    }
+}
+
+// the face table (indexed by side) and the roll table (indexed by move) of every orientation.
+// found by rolling around from the start orientation, which ends up at index 0.
+const ORIENTATIONS: ([[u8; 6]; 24], [[u8; 4]; 24]) = {
+    const fn roll(sides: [u8; 6], side: Side) -> [u8; 6] {
+        use Side::*;
+        let rotation = match side {
+            Side::Left  => [Left, Floor, Right, Sky],
+            Side::Right => [Right, Floor, Left, Sky],
+            Side::Down  => [Down, Floor, Up, Sky],
+            Side::Up    => [Up, Floor, Down, Sky],
+            _ => unreachable!()
+        };
+
+        let mut result = sides;
+        let mut i = 0;
+        while i < rotation.len() {
+            let from = rotation[i] as usize;
+            let to   = rotation[(i + 1) % rotation.len()] as usize;
+            result[to] = sides[from];
+            i += 1;
+        }
+        result
+    }
+
+    const fn find(faces: &[[u8; 6]; 24], count: usize, sides: [u8; 6]) -> usize {
+        let mut i = 0;
+        while i < count {
+            let mut j = 0;
+            while j < 6 && faces[i][j] == sides[j] {
+                j += 1;
+            }
+            if j == 6 {
+                return i;
+            }
+            i += 1;
+        }
+        count
+    }
+
+    let mut faces = [[0; 6]; 24];
+    let mut rolls = [[0; 4]; 24];
+    faces[0] = [1, 6, 4, 3, 5, 2];
+    let mut count = 1;
+
+    let mut i = 0;
+    while i < count {
+        let mut m = 0;
+        while m < MOVES.len() {
+            let next = roll(faces[i], MOVES[m]);
+            let j = find(&faces, count, next);
+            if j == count {
+                faces[count] = next;
+                count += 1;
+            }
+            rolls[i][m] = j as u8;
+            m += 1;
+        }
+        i += 1;
+    }
+    assert!(count == Orientation::COUNT);
+
+    (faces, rolls)
+};
+
+
+#[derive(Clone)]
+pub struct Dice {
+    pub pos: IVec2,
+    orientation: Orientation,
+    pub tail: Vec<(IVec2, u8)>,
+    // the dice before each restart, so restarts can be undone.
+    restarts: Vec<Restarted>,
+    // what was undone since the last new move, latest last.
+    redo: Vec<Redo>,
+    // for the roll anim.
+    pub prev_eyes: u8,
+    pub prev_pos: IVec2,
+}
+
+impl Dice {
+    pub fn new(pos: IVec2) -> Dice {
+        Dice {
+            pos,
+            orientation: Orientation::START,
+            tail: vec![],
+            restarts: vec![],
+            redo: vec![],
+            prev_eyes: 0,
+            prev_pos: pos,
+        }
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    pub fn get(&self, side: Side) -> u8 {
+        self.orientation.get(side)
+    }
+
+    pub fn eyes(&self) -> u8 {
+        self.get(Side::Sky)
+    }
+
+    pub fn on_tail(&self, target: IVec2) -> bool {
+        for (pos, _) in self.tail.iter() {
+            if *pos == target {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn rotate(&self, side: Side) -> Orientation {
+        self.orientation.roll(side)
+    }
+
+    pub fn move_thyself(&mut self, side: Side) {
+        self.prev_pos = self.pos;
+        self.prev_eyes = self.eyes();
+
+        self.tail.push((self.pos, self.get(Side::Floor)));
+        self.orientation = self.rotate(side);
+        self.pos  += side.unit();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.tail.is_empty() || !self.restarts.is_empty()
+    }
+
+    // takes back the last move, or the last restart once the moves since are gone.
+    // returns whether there was anything to take back.
+    pub fn undo(&mut self) -> bool {
+        let Some((pos, _)) = self.tail.pop() else {
+            let Some(restarted) = self.restarts.pop() else {
+                return false;
+            };
+            self.redo.push(Redo::Restart(self.pos));
+            self.pos = restarted.pos;
+            self.orientation = restarted.orientation;
+            self.tail = restarted.tail;
+            // it's a jump, not a roll.
+            self.prev_pos = self.pos;
+            self.prev_eyes = 0;
+            return true;
+        };
+
+        self.prev_pos = self.pos;
+        self.prev_eyes = self.eyes();
+
+        self.redo.push(Redo::Move(Side::from_unit(self.pos - pos)));
+        self.orientation = self.rotate(Side::from_unit(pos - self.pos));
+        self.pos = pos;
+        true
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    // does the last undone move or restart again. returns whether there was one.
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(Redo::Move(side)) => self.move_thyself(side),
+            Some(Redo::Restart(start)) => self.reset(start),
+            None => return false,
+        }
+        true
+    }
+
+    // back to `start` with a fresh dice, as a step that can be undone.
+    // returns false if the dice hasn't moved, there's nothing to restart then.
+    pub fn restart(&mut self, start: IVec2) -> bool {
+        if self.tail.is_empty() {
+            return false;
+        }
+        self.redo.clear();
+        self.reset(start);
+        true
+    }
+
+    fn reset(&mut self, start: IVec2) {
+        let tail = std::mem::take(&mut self.tail);
+        self.restarts.push(Restarted { pos: self.pos, orientation: self.orientation, tail });
+        self.pos = start;
+        self.orientation = Orientation::START;
+        self.prev_pos = start;
+        self.prev_eyes = 0;
+    }
+}
+
+#[derive(Clone)]
+struct Restarted {
+    pos: IVec2,
+    orientation: Orientation,
+    tail: Vec<(IVec2, u8)>,
+}
+
+#[derive(Clone, Copy)]
+enum Redo {
+    Move(Side),
+    // where the dice started.
+    Restart(IVec2),
+}
+
+
+// why `try_move` didn't move the dice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rejection {
+    Void,
+    // a goal for a different face.
+    WrongFace,
+    Tail,
+}
+
+pub fn try_move(dice: &mut Dice, level: &Level, side: Side) -> Result<(), Rejection> {
+    let target = dice.pos + side.unit();
+
+    if let Some((pos, _)) = dice.tail.last() {
+        if *pos == target {
+            dice.undo();
+            return Ok(());
+        }
+    }
+
+    if dice.on_tail(target) {
+        return Err(Rejection::Tail);
+    }
+    if !level.can_enter(target, dice.get(side)) {
+        if level.get(target.x, target.y) == ' ' {
+            return Err(Rejection::Void);
+        }
+        return Err(Rejection::WrongFace);
+    }
+
+    // a new move starts a new history.
+    dice.redo.clear();
+    dice.move_thyself(side);
+    Ok(())
+}
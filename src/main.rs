@@ -2,75 +2,18 @@ use lazy_static::lazy_static;
 use macroquad::prelude::*;
 use macroquad::audio::*;
 
+use jam_gmtk_2022::*;
+use jam_gmtk_2022::replay::{Action, Replay};
+use jam_gmtk_2022::save::Save;
+use jam_gmtk_2022::editor::Canvas;
 
-pub struct Level {
-    start: IVec2,
-    size:  IVec2,
-    tiles: Vec<char>,
-    goals: Vec<IVec2>,
-}
-
-impl Level {
-    pub fn parse(level: &[&str]) -> Level {
-        let mut start = None;
-        let mut width  = 0;
-        let mut height = 0;
-        let mut tiles = vec![];
-        let mut goals = vec![];
-
-        for line in level {
-            if height == 0 {
-                width = line.len();
-            }
-            assert!(line.len() == width);
-
-            let y = height;
-            height += 1;
-
-            for (x, mut tile) in line.chars().enumerate() {
-                assert!(" .123456s".contains(tile));
-                let pos = IVec2::new(x as i32, y);
-
-                if tile == 's' {
-                    assert!(start.is_none());
-                    start = Some(pos);
-                    tile = '.';
-                }
-
-                if "123456".contains(tile) {
-                    goals.push(pos);
-                }
-
-                tiles.push(tile);
-            }
-        }
 
-        let start = start.unwrap();
-        let size  = IVec2::new(width as i32, height);
-        Level { start, size, tiles, goals }
-    }
-
-    pub fn get(&self, x: i32, y: i32) -> char {
-        if x >= 0 && x < self.size.x && y >= 0 && y < self.size.y {
-            self.tiles[(y*self.size.x + x) as usize]
-        }
-        else {
-            ' '
-        }
-    }
-
-    pub fn to_goal(tile: char) -> Option<u8> {
-        if "123456".contains(tile) {
-            return Some((tile as u8) - ('1' as u8) + 1);
-        }
-        None
-    }
-
-    pub fn detect_win(&self, dice: &Dice) -> bool {
-        self.goals.iter().all(|goal| dice.on_tail(*goal) || *goal == dice.pos)
-    }
+pub trait RenderLevel {
+    fn render(&self, origin: Vec2, tile_size: Vec2, t: f32);
+}
 
-    pub fn render(&self, origin: Vec2, tile_size: Vec2, _t: f32) {
+impl RenderLevel for Level {
+    fn render(&self, origin: Vec2, tile_size: Vec2, _t: f32) {
         for y in 0..self.size.y + 1 {
             for x in 0..self.size.x {
                 let pos = origin + Vec2::new(x as f32, y as f32)*tile_size;
@@ -92,7 +35,7 @@ impl Level {
                     .. Default::default()
                 });
 
-                if let Some(count) = Self::to_goal(tile) {
+                if let Some(count) = Level::to_goal(tile) {
                     draw_eyes(count, pos, tile_size, Color::from_rgba(23, 22, 38, 128));
 
                     let s = 0.875 + ((2.5 * get_time()).sin().abs() as f32)*0.125;
@@ -106,77 +49,12 @@ impl Level {
 }
 
 
-#[derive(Clone, Copy, PartialEq)]
-#[repr(usize)]
-pub enum Side {
-    Floor = 0,
-    Sky   = 1,
-    Left  = 2,
-    Right = 3,
-    Down  = 4,
-    Up    = 5,
-}
-
-impl Side {
-    pub fn unit(self) -> IVec2 {
-        match self {
-            Side::Left  => IVec2::new(-1, 0),
-            Side::Right => IVec2::new( 1, 0),
-            Side::Down  => IVec2::new(0,  1),
-            Side::Up    => IVec2::new(0, -1),
-            _ => unreachable!()
-        }
-    }
-
-    pub fn from_unit(unit: IVec2) -> Side {
-        match (unit.x, unit.y) {
-            (-1, 0) => Side::Left,
-            ( 1, 0) => Side::Right,
-            (0,  1) => Side::Down,
-            (0, -1) => Side::Up,
-            _ => unreachable!()
-        }
-    }
-}
-
-
-pub struct Dice {
-    pos: IVec2,
-    sides: [u8; 6],
-    tail: Vec<(IVec2, u8)>,
-    prev_eyes: u8,
-    prev_pos: IVec2,
+pub trait RenderDice {
+    fn render(&self, origin: Vec2, tile_size: Vec2, level: &Level, t: f32);
 }
 
-impl Dice {
-    pub fn new(pos: IVec2) -> Dice {
-        Dice {
-            pos,
-            sides: [1, 6, 4, 3, 5, 2],
-            tail: vec![],
-            prev_eyes: 0,
-            prev_pos: pos,
-        }
-    }
-
-    pub fn get(&self, side: Side) -> u8 {
-        self.sides[side as usize]
-    }
-
-    pub fn eyes(&self) -> u8 {
-        self.get(Side::Sky)
-    }
-
-    pub fn on_tail(&self, target: IVec2) -> bool {
-        for (pos, _) in self.tail.iter() {
-            if *pos == target {
-                return true;
-            }
-        }
-        false
-    }
-
-    pub fn render(&self, origin: Vec2, tile_size: Vec2, level: &Level, t: f32) {
+impl RenderDice for Dice {
+    fn render(&self, origin: Vec2, tile_size: Vec2, level: &Level, t: f32) {
         let eye_color = Color::from_rgba(23, 22, 38, 255);
 
         let mut pos = origin + self.pos.as_f32()*tile_size;
@@ -198,7 +76,7 @@ impl Dice {
 
             // eyes
             let mut c = eye_color; c.a = 0.5;
-            if let Some(_) = Level::to_goal(level.get(pos.x, pos.y)) {
+            if Level::to_goal(level.get(pos.x, pos.y)).is_some() {
                 c = Color::from_rgba(103, 175, 65, 200);
             }
             draw_eyes(count, draw_pos, tile_size, c);
@@ -235,49 +113,11 @@ impl Dice {
 
         draw_dice(curr_pos, curr_size, self.eyes(), eye_color);
     }
-
-    pub fn rotate(&self, side: Side) -> [u8; 6] {
-        use Side::*;
-        let rotation = match side {
-            Side::Left  => [Left, Floor, Right, Sky],
-            Side::Right => [Right, Floor, Left, Sky],
-            Side::Down  => [Down, Floor, Up, Sky],
-            Side::Up    => [Up, Floor, Down, Sky],
-            _ => unreachable!()
-        };
-
-        let mut sides = self.sides;
-        for i in 0..rotation.len() {
-            let from = rotation[i] as usize;
-            let to   = rotation[(i + 1) % rotation.len()] as usize;
-            sides[to] = self.sides[from];
-        }
-
-        sides
-    }
-
-    pub fn move_thyself(&mut self, side: Side) {
-        self.prev_pos = self.pos;
-        self.prev_eyes = self.eyes();
-
-        self.tail.push((self.pos, self.get(Side::Floor)));
-        self.sides = self.rotate(side);
-        self.pos  += side.unit();
-    }
-
-    pub fn undo(&mut self) {
-        self.prev_pos = self.pos;
-        self.prev_eyes = self.eyes();
-
-        let (pos, _) = self.tail.pop().unwrap();
-        self.sides = self.rotate(Side::from_unit(pos - self.pos));
-        self.pos = pos;
-    }
 }
 
 
 pub fn draw_eyes(count: u8, pos: Vec2, size: Vec2, color: Color) {
-    assert!(count >= 1 && count <= 6);
+    assert!((1..=6).contains(&count));
     draw_texture_ex(TEX_EYES[(count - 1) as usize], pos.x, pos.y, color, DrawTextureParams {
         dest_size: Some(size),
         .. Default::default()
@@ -293,16 +133,20 @@ pub fn draw_dice(pos: Vec2, size: Vec2, eye_count: u8, eye_color: Color) {
     draw_eyes(eye_count, pos, size, eye_color);
 }
 
-pub fn draw_moves(level: &Level, dice: &Dice, origin: Vec2, tile_size: Vec2) {
+pub fn draw_moves(level: &Level, dice: &Dice, origin: Vec2, tile_size: Vec2, hint: Option<Side>) {
     for side in [Side::Left, Side::Right, Side::Down, Side::Up] {
         let target = dice.pos + side.unit();
+        let draw_pos = origin + target.as_f32()*tile_size;
+
+        if hint == Some(side) {
+            let s = 0.625 + ((5.0 * get_time()).sin().abs() as f32)*0.375;
+            draw_goal(draw_pos, tile_size, Color::new(1.0, 1.0, 1.0, s));
+        }
 
         if dice.on_tail(target) {
             continue;
         }
 
-        let draw_pos = origin + target.as_f32()*tile_size;
-
         let tile = level.get(target.x, target.y);
         if tile == '.' {
             draw_eyes(dice.get(side), draw_pos, tile_size, Color::new(1.0, 1.0, 1.0, 0.25));
@@ -318,9 +162,22 @@ pub fn draw_moves(level: &Level, dice: &Dice, origin: Vec2, tile_size: Vec2) {
     }
 }
 
+// where a board of `size` tiles goes on screen, and how big its tiles are.
+pub fn board_layout(size: IVec2) -> (Vec2, Vec2) {
+    let sx = screen_width()  / size.x as f32;
+    let sy = screen_height() / size.y as f32;
+    let s = (sx.min(sy) / 1.5).floor().min(150.0);
+    let tile_size = Vec2::splat(s);
+
+    let board_size = size.as_f32() * tile_size;
+    let screen_size = Vec2::new(screen_width(), screen_height());
+    let origin = (screen_size/2.0 - board_size/2.0).floor();
+    (origin, tile_size)
+}
+
 pub fn draw_border(pos: Vec2, size: Vec2, mask: [bool; 6], color: Color) {
     let index =
-          (!mask[Side::Left  as usize] as usize) << 0
+          (!mask[Side::Left  as usize] as usize)
         | (!mask[Side::Up    as usize] as usize) << 1
         | (!mask[Side::Right as usize] as usize) << 2
         | (!mask[Side::Down  as usize] as usize) << 3;
@@ -356,10 +213,233 @@ pub fn draw_background(origin: Vec2, tile_size: Vec2) {
     }
 }
 
+// a slow red pulse around the dice, when the level can't be won anymore.
+pub fn draw_dead_end(pos: Vec2, size: Vec2) {
+    let s = 0.25 + ((1.5 * get_time()).sin().abs() as f32)*0.35;
+    draw_goal(pos, size, Color::new(216.0/255.0, 59.0/255.0, 39.0/255.0, s));
+}
+
+// the tail prints light up green one after another, from the start to the dice.
+// `t` is the time since the win, in seconds.
+pub fn draw_win(dice: &Dice, origin: Vec2, tile_size: Vec2, t: f32) {
+    for (i, (pos, count)) in dice.tail.iter().enumerate() {
+        let k = t - i as f32 * 0.04;
+        if k <= 0.0 {
+            break;
+        }
+
+        let s = (4.0*k).min(1.0) * (0.5 + ((6.0*k).sin().abs())*0.5);
+        let c = Color::from_rgba(103, 175, 65, 255).to_vec() * Vec4::new(1.0, 1.0, 1.0, s);
+        let draw_pos = origin + pos.as_f32()*tile_size;
+        draw_eyes(*count, draw_pos, tile_size, Color::from_vec(c));
+        draw_goal(draw_pos, tile_size, Color::from_vec(c));
+    }
+}
+
+// covers the screen, for fading between levels.
+pub fn draw_fade(alpha: f32) {
+    if alpha <= 0.0 {
+        return;
+    }
+    draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(23.0/255.0, 22.0/255.0, 38.0/255.0, alpha));
+}
+
+// a flash on the tile the dice bumped into, fading with `t`.
+// red off the edge, amber on a goal for another face, dark on the tail.
+pub fn draw_bump(pos: Vec2, size: Vec2, t: f32, rejection: Rejection) {
+    let mut color = match rejection {
+        Rejection::Void => Color::from_rgba(216, 59, 39, 255),
+        Rejection::WrongFace => Color::from_rgba(232, 159, 39, 255),
+        Rejection::Tail => Color::from_rgba(23, 22, 38, 255),
+    };
+    color.a = 1.0 - t;
+    draw_goal(pos, size, color);
+}
+
 pub fn draw_goal(pos: Vec2, size: Vec2, color: Color) {
     draw_border(pos, size, [true; 6], color)
 }
 
+// only shows what the level's headers provide.
+pub fn draw_hud(level: &Level, dice: &Dice) {
+    let font_size = 32.0;
+    let color = Color::from_rgba(23, 22, 38, 200);
+
+    let mut top = String::new();
+    if let Some(name) = &level.meta.name {
+        top += name;
+    }
+    if let Some(par) = level.meta.par {
+        if !top.is_empty() {
+            top += "  -  ";
+        }
+        top += &format!("{} / {}", dice.tail.len(), par);
+    }
+    if !top.is_empty() {
+        draw_centered_text(&top, 1.5*font_size, font_size, color);
+    }
+
+    if let Some(hint) = &level.meta.hint {
+        draw_centered_text(hint, screen_height() - font_size, font_size, color);
+    }
+}
+
+pub fn draw_centered_text(text: &str, y: f32, font_size: f32, color: Color) {
+    let size = measure_text(text, None, font_size as u16, 1.0);
+    draw_text(text, (screen_width() - size.width)/2.0, y, font_size, color);
+}
+
+pub fn draw_menu(entries: &[&str]) {
+    let font_size = 32.0;
+    let bottom = screen_height() - font_size;
+    for (i, entry) in entries.iter().rev().enumerate() {
+        draw_centered_text(entry, bottom - i as f32 * 1.25*font_size, font_size, Color::from_rgba(23, 22, 38, 200));
+    }
+}
+
+pub fn draw_daily(day: u64, result: Option<&daily::DailyResult>) {
+    let font_size = 48.0;
+    let color = Color::from_rgba(23, 22, 38, 255);
+    let y = screen_height()/2.0 - 2.0*font_size;
+
+    draw_centered_text("daily puzzle", y, font_size, color);
+    draw_centered_text(&daily::date(day), y + 1.25*font_size, font_size, color);
+
+    let status = match result {
+        Some(result) if result.solved => {
+            let seconds = result.seconds as u64;
+            format!("solved in {} moves, {}:{:02}", result.moves, seconds / 60, seconds % 60)
+        }
+        Some(_) => "not solved yet - keep trying".to_string(),
+        None    => "not played yet".to_string(),
+    };
+    draw_centered_text(&status, y + 3.0*font_size, 0.75*font_size, color);
+
+    draw_menu(&["enter - play", "escape - back"]);
+}
+
+// levels go in a square-ish grid, left to right, top to bottom.
+pub fn level_select_columns(count: usize) -> usize {
+    ((count as f32).sqrt().ceil() as usize).max(1)
+}
+
+// the screen area of a level's thumbnail, between the title and the menu.
+pub fn level_select_cell(index: usize, count: usize) -> Rect {
+    let font_size = 32.0;
+    let columns = level_select_columns(count);
+    let rows = count.div_ceil(columns).max(1);
+
+    let top = 2.5*font_size;
+    let bottom = screen_height() - 3.5*font_size;
+    let cell = ((screen_width() - font_size) / columns as f32).min((bottom - top) / rows as f32).floor();
+
+    let left = (screen_width() - cell*columns as f32) / 2.0;
+    let x = left + (index % columns) as f32 * cell;
+    let y = top  + (index / columns) as f32 * cell;
+    Rect::new(x, y, cell, cell)
+}
+
+pub fn draw_level_select(levels: &[Level], save: &Save, selected: usize) {
+    let font_size = 32.0;
+    let color = Color::from_rgba(23, 22, 38, 200);
+    let green = Color::from_rgba(103, 175, 65, 255);
+
+    draw_centered_text("levels", 1.5*font_size, font_size, color);
+
+    for (i, level) in levels.iter().enumerate() {
+        let cell = level_select_cell(i, levels.len());
+
+        // leave room for the label, and for the grass below the last row.
+        let room = Vec2::new(cell.w, cell.h - font_size) * 0.85;
+        let s = (room.x / level.size.x as f32).min(room.y / (level.size.y + 1) as f32).floor().max(1.0);
+        let tile_size = Vec2::splat(s);
+        let board_size = level.size.as_f32() * tile_size;
+        let origin = (Vec2::new(cell.x + cell.w/2.0, cell.y + (cell.h - font_size)/2.0) - board_size/2.0).floor();
+
+        level.render(origin, tile_size, 1.0);
+
+        if i == selected {
+            let s = 0.5 + ((5.0 * get_time()).sin().abs() as f32)*0.5;
+            draw_rectangle_lines(cell.x + 4.0, cell.y + 4.0, cell.w - 8.0, cell.h - 8.0, 4.0, Color::new(1.0, 1.0, 1.0, s));
+        }
+
+        let (label, label_color) = match save.best_moves(level) {
+            Some(best) => (format!("{} - best {}", i + 1, best), green),
+            None       => (format!("{}", i + 1), color),
+        };
+        let size = measure_text(&label, None, (0.75*font_size) as u16, 1.0);
+        draw_text(&label, cell.x + (cell.w - size.width)/2.0, cell.y + cell.h - 0.5*font_size, 0.75*font_size, label_color);
+    }
+
+    draw_menu(&["enter - play", "escape - back"]);
+}
+
+pub fn brush_name(tile: char) -> String {
+    match tile {
+        ' ' => "void".to_string(),
+        '.' => "floor".to_string(),
+        's' => "start".to_string(),
+        _   => format!("goal {}", tile),
+    }
+}
+
+pub fn draw_editor(canvas: &Canvas, brush: char, message: Option<&str>) {
+    let (origin, tile_size) = board_layout(canvas.size);
+    draw_background(origin, tile_size);
+
+    canvas.to_uncropped_level().render(origin, tile_size, 1.0);
+
+    let board_size = canvas.size.as_f32() * tile_size;
+    draw_rectangle_lines(origin.x, origin.y, board_size.x, board_size.y, 2.0, Color::new(1.0, 1.0, 1.0, 0.5));
+
+    draw_dice(origin + canvas.start().as_f32()*tile_size, tile_size, 1, Color::from_rgba(23, 22, 38, 255));
+
+    let hover = ((Vec2::from(mouse_position()) - origin) / tile_size).floor().as_i32();
+    if canvas.contains(hover) {
+        draw_goal(origin + hover.as_f32()*tile_size, tile_size, Color::new(1.0, 1.0, 1.0, 0.5));
+    }
+
+    let font_size = 32.0;
+    let color = Color::from_rgba(23, 22, 38, 200);
+    draw_centered_text(&format!("brush: {}", brush_name(brush)), 1.5*font_size, font_size, color);
+    if let Some(message) = message {
+        draw_centered_text(message, 2.75*font_size, font_size, color);
+    }
+
+    draw_menu(&[
+        "1-6 goal  . floor  space void  s start",
+        "left mouse - paint  right mouse - erase",
+        "enter - test  ctrl+s - save  escape - back",
+    ]);
+}
+
+pub fn draw_hint(hint: solver::Hint) {
+    let text = match hint {
+        solver::Hint::Move(_) | solver::Hint::Won => return,
+        solver::Hint::Undo(1) => "no way to win from here - undo 1 move".to_string(),
+        solver::Hint::Undo(undos) => format!("no way to win from here - undo {} moves", undos),
+        solver::Hint::Impossible => "this level can't be won".to_string(),
+    };
+
+    let font_size = 32.0;
+    draw_centered_text(&text, screen_height() - 2.5*font_size, font_size, Color::from_rgba(216, 59, 39, 255));
+}
+
+pub fn draw_errors(errors: &[LevelParseError]) {
+    if errors.is_empty() {
+        return;
+    }
+
+    let font_size = 24.0;
+    let height = (errors.len() as f32 + 1.0) * font_size;
+    draw_rectangle(0.0, 0.0, screen_width(), height, Color::from_rgba(23, 22, 38, 220));
+
+    for (i, error) in errors.iter().enumerate() {
+        let y = (i as f32 + 1.0) * font_size;
+        draw_text(&error.to_string(), 10.0, y, font_size, Color::from_rgba(216, 59, 39, 255));
+    }
+}
+
 pub fn play_step() {
     let i = rand::rand() as usize % SND_STEPS.len();
     play_sound_once(SND_STEPS[i]);
@@ -370,35 +450,63 @@ pub fn play_goal() {
     play_sound_once(SND_GOAL[i]);
 }
 
-pub fn try_move(dice: &mut Dice, level: &Level, side: Side) -> bool {
-    let target = dice.pos + side.unit();
+// the edge gets the full bump, the others are softer.
+pub fn play_bump(rejection: Rejection) {
+    let volume = match rejection {
+        Rejection::Void => 1.0,
+        Rejection::WrongFace => 0.6,
+        Rejection::Tail => 0.4,
+    };
+    play_sound(*SND_BUMP, PlaySoundParams { looped: false, volume });
+}
 
-    if let Some((pos, _)) = dice.tail.last() {
-        if *pos == target {
-            dice.undo();
-            return true;
-        }
+// the board tile under the mouse.
+pub fn pointer_tile(level: &Level) -> IVec2 {
+    let (origin, tile_size) = board_layout(level.size);
+    ((Vec2::from(mouse_position()) - origin) / tile_size).floor().as_i32()
+}
+
+pub fn is_neighbour(a: IVec2, b: IVec2) -> bool {
+    let d = (a - b).abs();
+    d.x + d.y == 1
+}
+
+// one step from the dice towards `target`, along the longer axis.
+// stepping back onto the tail is an undo.
+pub fn step_towards(dice: &Dice, target: IVec2) -> Option<Action> {
+    let d = target - dice.pos;
+    if d == IVec2::ZERO {
+        return None;
     }
 
-    if dice.on_tail(target) {
-        return false;
+    let unit = if d.x.abs() >= d.y.abs() { IVec2::new(d.x.signum(), 0) } else { IVec2::new(0, d.y.signum()) };
+    if dice.tail.last().map(|(pos, _)| *pos) == Some(dice.pos + unit) {
+        return Some(Action::Undo);
     }
+    Some(Action::Move(Side::from_unit(unit)))
+}
+
+// `Err(None)` when there was nothing to undo, redo or restart.
+pub fn apply(action: Action, dice: &mut Dice, level: &Level) -> Result<(), Option<Rejection>> {
+    match action {
+        Action::Move(side) => try_move_with_sound(dice, level, side).map_err(Some),
+        _ if action.apply(dice, level) => Ok(()),
+        _ => Err(None),
+    }
+}
 
-    let tile = level.get(target.x, target.y);
+pub fn try_move_with_sound(dice: &mut Dice, level: &Level, side: Side) -> Result<(), Rejection> {
+    let tail_len = dice.tail.len();
 
-    if tile == ' ' {
-        return false;
+    if let Err(rejection) = try_move(dice, level, side) {
+        play_bump(rejection);
+        return Err(rejection);
     }
 
-    if let Some(count) = Level::to_goal(tile) {
-        if count != dice.get(side) {
-            return false;
-        }
+    if dice.tail.len() > tail_len && Level::to_goal(level.get(dice.pos.x, dice.pos.y)).is_some() {
         play_goal();
     }
-
-    dice.move_thyself(side);
-    true
+    Ok(())
 }
 
 
@@ -421,6 +529,197 @@ impl Anim {
 }
 
 
+// a spark flying off a goal when the level is won.
+// positions are in tiles, so they stay put on the board when the window resizes.
+struct Particle {
+    pos: Vec2,
+    vel: Vec2,
+    born: f64,
+    life: f64,
+    color: Color,
+}
+
+impl Particle {
+    const GRAVITY: f32 = 8.0;
+
+    fn alive(&self, now: f64) -> bool {
+        now - self.born < self.life
+    }
+
+    fn draw(&self, origin: Vec2, tile_size: Vec2, now: f64) {
+        let t = (now - self.born) as f32;
+        let pos = self.pos + self.vel*t + Vec2::new(0.0, 0.5*Particle::GRAVITY*t*t);
+        let size = tile_size * 0.08 * (1.0 - t / self.life as f32);
+
+        let screen_pos = origin + pos*tile_size - size/2.0;
+        draw_rectangle(screen_pos.x, screen_pos.y, size.x, size.y, self.color);
+    }
+}
+
+fn spawn_particles(particles: &mut Vec<Particle>, pos: Vec2, now: f64) {
+    for _ in 0..16 {
+        let angle = rand::gen_range(0.0, std::f32::consts::TAU);
+        let speed = rand::gen_range(1.5, 4.0);
+        let color = if rand::gen_range(0, 3) == 0 { WHITE } else { Color::from_rgba(103, 175, 65, 255) };
+        particles.push(Particle {
+            pos,
+            vel: Vec2::new(angle.cos(), angle.sin() - 1.0) * speed,
+            born: now,
+            life: rand::gen_range(0.6, 1.1),
+            color,
+        });
+    }
+}
+
+
+// INPUT
+
+// what the player can ask for, on the keyboard or a gamepad.
+#[derive(Clone, Copy, PartialEq)]
+enum Button {
+    Left,
+    Right,
+    Down,
+    Up,
+    Undo,
+    Redo,
+    Restart,
+    Hint,
+    Confirm,
+    Back,
+}
+
+impl Button {
+    const ALL: [Button; 10] = [
+        Button::Left, Button::Right, Button::Down, Button::Up,
+        Button::Undo, Button::Redo, Button::Restart, Button::Hint,
+        Button::Confirm, Button::Back,
+    ];
+
+    fn keys(self) -> &'static [KeyCode] {
+        match self {
+            Button::Left    => &[KeyCode::Left, KeyCode::A],
+            Button::Right   => &[KeyCode::Right, KeyCode::D],
+            Button::Down    => &[KeyCode::Down, KeyCode::S],
+            Button::Up      => &[KeyCode::Up, KeyCode::W],
+            Button::Undo    => &[KeyCode::Z],
+            Button::Redo    => &[KeyCode::Y],
+            Button::Restart => &[KeyCode::R],
+            Button::Hint    => &[KeyCode::H],
+            Button::Confirm => &[KeyCode::Enter],
+            Button::Back    => &[KeyCode::Escape],
+        }
+    }
+
+    // the left stick counts as the d-pad too, see `Input::update`.
+    fn pad_buttons(self) -> &'static [gilrs::Button] {
+        match self {
+            Button::Left    => &[gilrs::Button::DPadLeft],
+            Button::Right   => &[gilrs::Button::DPadRight],
+            Button::Down    => &[gilrs::Button::DPadDown],
+            Button::Up      => &[gilrs::Button::DPadUp],
+            Button::Undo    => &[gilrs::Button::West],
+            Button::Redo    => &[gilrs::Button::RightTrigger],
+            Button::Restart => &[gilrs::Button::North],
+            Button::Hint    => &[gilrs::Button::LeftTrigger],
+            Button::Confirm => &[gilrs::Button::South, gilrs::Button::Start],
+            Button::Back    => &[gilrs::Button::East, gilrs::Button::Select],
+        }
+    }
+}
+
+struct Input {
+    // no gamepads where gilrs can't start. the keyboard still works then.
+    gilrs: Option<gilrs::Gilrs>,
+    // which buttons any gamepad holds, this frame and the last.
+    pad: [bool; Button::ALL.len()],
+    prev_pad: [bool; Button::ALL.len()],
+}
+
+impl Input {
+    // how far the stick has to go before it counts as a direction.
+    const DEADZONE: f32 = 0.5;
+
+    pub fn new() -> Input {
+        Input {
+            gilrs: gilrs::Gilrs::new().ok(),
+            pad: [false; Button::ALL.len()],
+            prev_pad: [false; Button::ALL.len()],
+        }
+    }
+
+    // once per frame, before asking about buttons.
+    pub fn update(&mut self) {
+        self.prev_pad = self.pad;
+        self.pad = [false; Button::ALL.len()];
+
+        let Some(gilrs) = &mut self.gilrs else {
+            return;
+        };
+        // gilrs only updates the gamepad state while handing out events.
+        while gilrs.next_event().is_some() {}
+
+        for (_, pad) in gilrs.gamepads() {
+            for button in Button::ALL {
+                if button.pad_buttons().iter().any(|b| pad.is_pressed(*b)) {
+                    self.pad[button as usize] = true;
+                }
+            }
+
+            let stick = Vec2::new(pad.value(gilrs::Axis::LeftStickX), pad.value(gilrs::Axis::LeftStickY));
+            if stick.length() > Input::DEADZONE {
+                let button =
+                    if stick.x.abs() > stick.y.abs() {
+                        if stick.x < 0.0 { Button::Left } else { Button::Right }
+                    }
+                    else if stick.y > 0.0 { Button::Up } else { Button::Down };
+                self.pad[button as usize] = true;
+            }
+        }
+    }
+
+    pub fn pressed(&self, button: Button) -> bool {
+        self.key(button, is_key_pressed) || (self.pad[button as usize] && !self.prev_pad[button as usize])
+    }
+
+    pub fn down(&self, button: Button) -> bool {
+        self.key(button, is_key_down) || self.pad[button as usize]
+    }
+
+    fn key(&self, button: Button, check: fn(KeyCode) -> bool) -> bool {
+        let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+        match button {
+            Button::Undo => check(KeyCode::Z) && !shift,
+            Button::Redo => check(KeyCode::Y) || (check(KeyCode::Z) && shift),
+            _ => button.keys().iter().any(|key| check(*key)),
+        }
+    }
+
+    // the action of the first button pressed this frame.
+    pub fn pressed_action(&self) -> Option<Action> {
+        Input::action(|button| self.pressed(button))
+    }
+
+    // the action of the first button held down. restarting doesn't repeat.
+    pub fn held_action(&self) -> Option<Action> {
+        Input::action(|button| self.down(button)).filter(|action| *action != Action::Restart)
+    }
+
+    fn action(check: impl Fn(Button) -> bool) -> Option<Action> {
+        let actions = [
+            (Button::Left,    Action::Move(Side::Left)),
+            (Button::Right,   Action::Move(Side::Right)),
+            (Button::Down,    Action::Move(Side::Down)),
+            (Button::Up,      Action::Move(Side::Up)),
+            (Button::Redo,    Action::Redo),
+            (Button::Undo,    Action::Undo),
+            (Button::Restart, Action::Restart),
+        ];
+        actions.iter().find(|(button, _)| check(*button)).map(|(_, action)| *action)
+    }
+}
+
+
 // TEXTURES
 
 pub fn load_texture(bytes: &[u8]) -> Texture2D {
@@ -478,39 +777,36 @@ pub async fn load_sound(bytes: &[u8]) -> Sound {
 
 static mut _SND_STEPS: Option<[Sound; 4]> = None;
 static mut _SND_GOAL: Option<[Sound; 2]> = None;
+static mut _SND_BUMP: Option<Sound> = None;
 
 lazy_static!(
     static ref SND_STEPS: [Sound; 4] = unsafe { _SND_STEPS.unwrap() };
     static ref SND_GOAL: [Sound; 2] = unsafe { _SND_GOAL.unwrap() };
+    static ref SND_BUMP: Sound = unsafe { _SND_BUMP.unwrap() };
 );
 
 
 #[macroquad::main("gmtk-2022")]
 async fn main() {
 
-    fn parse_levels(levels: &str) -> Vec<Level> {
-        levels.split("\n\n")
-        .map(|lines|
-            Level::parse(
-                &lines.split("\n")
-                .filter(|line| line.len() > 0)
-                .collect::<Vec<_>>())
-        ).collect()
-    }
-
-    fn load(levels: &str) -> (Vec<Level>, usize, Dice) {
-        let levels = parse_levels(levels);
+    fn load(levels: &str) -> Result<(Vec<Level>, usize, Dice), Vec<LevelParseError>> {
+        let levels = parse_levels(levels)?;
         let level_index = 0;
         let dice = Dice::new(levels[0].start);
-        (levels, level_index, dice)
+        Ok((levels, level_index, dice))
     }
 
-    fn hot_load() -> (Vec<Level>, usize, Dice) {
+    fn hot_load() -> Result<(Vec<Level>, usize, Dice), Vec<LevelParseError>> {
         #[cfg(debug_assertions)] {
             load(&String::from_utf8(std::fs::read("src/levels.txt").unwrap()).unwrap())
         }
         #[cfg(not(debug_assertions))] {
-            load(std::str::from_utf8(include_bytes!("levels.txt")).unwrap())
+            // the shipped levels are built in, so the editor's levels come after them.
+            let (mut levels, level_index, dice) = load(std::str::from_utf8(include_bytes!("levels.txt")).unwrap())?;
+            if let Some(text) = data_path(EDITOR_FILE).and_then(|path| std::fs::read_to_string(path).ok()) {
+                levels.extend(parse_levels(&text)?);
+            }
+            Ok((levels, level_index, dice))
         }
     }
 
@@ -532,6 +828,40 @@ async fn main() {
     }
 
 
+    // the daily puzzle is played as a pack of one level.
+    // the real pack waits here until it's over.
+    struct DailyRun {
+        day: u64,
+        start: f64,
+        pack: Vec<Level>,
+        pack_index: usize,
+    }
+
+    const DAILY_FILE: &str = "daily.txt";
+
+    fn load_daily_results() -> Vec<daily::DailyResult> {
+        data_path(DAILY_FILE)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|text| daily::parse_results(&text))
+            .unwrap_or_default()
+    }
+
+    fn end_daily(run: DailyRun, solved: bool, now: f64, levels: &mut Vec<Level>, level_index: &mut usize, dice: &mut Dice) {
+        let mut results = load_daily_results();
+        daily::record(&mut results, daily::DailyResult {
+            day: run.day,
+            solved,
+            moves: dice.tail.len() as u32,
+            seconds: now - run.start,
+        });
+        // nowhere to save on some platforms. the result just doesn't stick then.
+        let _ = write_data(DAILY_FILE, &daily::write_results(&results));
+
+        *levels = run.pack;
+        set_level(run.pack_index, levels, level_index, dice);
+    }
+
+
 
     // load sounds.
     unsafe {
@@ -545,78 +875,421 @@ async fn main() {
             load_sound(include_bytes!("sound/goal-0.wav")).await,
             load_sound(include_bytes!("sound/goal-1.wav")).await,
         ]);
+        _SND_BUMP = Some(load_sound(include_bytes!("sound/bump-0.wav")).await);
+    }
+
+
+    // feeds a replay through the same path as the keyboard.
+    struct Playback {
+        replay: Replay,
+        next: usize,
+        start: f64,
+    }
+
+    impl Playback {
+        // events without a time go as soon as the dice is ready.
+        fn next(&mut self, now: f64) -> Option<Action> {
+            let event = self.replay.events.get(self.next)?;
+            if event.time.map(|time| now - self.start < time).unwrap_or(false) {
+                return None;
+            }
+            self.next += 1;
+            Some(event.action)
+        }
+
+        fn done(&self) -> bool {
+            self.next >= self.replay.events.len()
+        }
+    }
+
+    // relative to the config dir, like the save.
+    // and like the save, levels go by `Level::content_hash`, so editing the pack doesn't mix them up.
+    fn replay_path(level: &Level, daily: &Option<DailyRun>, suffix: &str) -> String {
+        match daily {
+            Some(run) => format!("replays/daily-{}{}.txt", daily::date(run.day), suffix),
+            None => format!("replays/level-{:016x}{}.txt", level.content_hash(), suffix),
+        }
+    }
+
+    fn save_replay(path: &str, replay: &Replay) {
+        // like the daily results, replays just don't get saved where there's no file system.
+        let _ = write_data(path, &format!("{}\n", replay));
+    }
+
+    fn load_replay(path: &str) -> Option<Replay> {
+        Replay::parse(&std::fs::read_to_string(data_path(path)?).ok()?).ok()
+    }
+
+
+    // everything the game writes goes in the platform's config dir,
+    // so it doesn't depend on where the game was started.
+    fn data_path(name: &str) -> Option<std::path::PathBuf> {
+        Some(dirs::config_dir()?.join("a-tail-of-dice").join(name))
+    }
+
+    // makes the directories on the way.
+    fn write_data(name: &str, contents: &str) -> std::io::Result<()> {
+        let path = data_path(name).ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no config dir"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, contents)
+    }
+
+    const SAVE_FILE: &str = "save.txt";
+
+    fn load_save() -> Save {
+        data_path(SAVE_FILE)
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .map(|text| Save::parse(&text))
+            .unwrap_or_default()
+    }
+
+    fn write_save(save: &Save) {
+        let _ = write_data(SAVE_FILE, &save.to_string());
     }
 
 
+    // test plays from the editor swap the level in, like the daily puzzle.
+    struct TestRun {
+        pack: Vec<Level>,
+        pack_index: usize,
+    }
+
+    fn end_test(run: TestRun, levels: &mut Vec<Level>, level_index: &mut usize, dice: &mut Dice) {
+        *levels = run.pack;
+        set_level(run.pack_index, levels, level_index, dice);
+    }
+
+    // release builds can't change the shipped levels, so the editor's go in their own file.
+    #[cfg(not(debug_assertions))]
+    const EDITOR_FILE: &str = "my-levels.txt";
+
+    // where the editor saves to. always a file `hot_load` reads, so F5 picks the level up.
+    fn levels_path() -> Option<std::path::PathBuf> {
+        #[cfg(debug_assertions)] {
+            Some("src/levels.txt".into())
+        }
+        #[cfg(not(debug_assertions))] {
+            data_path(EDITOR_FILE)
+        }
+    }
+
+    // how many of the loaded levels come before the ones in `levels_path`.
+    fn levels_path_offset() -> usize {
+        #[cfg(debug_assertions)] {
+            0
+        }
+        #[cfg(not(debug_assertions))] {
+            parse_levels(std::str::from_utf8(include_bytes!("levels.txt")).unwrap()).map_or(0, |levels| levels.len())
+        }
+    }
+
+    // saves over level `index` when it's in the file, adds the level at the end otherwise.
+    // returns where it went, and the level's new index.
+    fn save_level(level: &Level, index: Option<usize>) -> std::io::Result<(std::path::PathBuf, usize)> {
+        let path = levels_path().ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no config dir"))?;
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+
+        let offset = levels_path_offset();
+        let in_file = index.and_then(|index| index.checked_sub(offset));
+        let (text, index) = match in_file.and_then(|i| Some((replace_level(&text, i, level)?, i))) {
+            Some((text, i)) => (text, offset + i),
+            None => (append_level(&text, level), offset + parse_each_level(&text).0.len()),
+        };
+
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&path, text)?;
+        Ok((path, index))
+    }
+
+
+    // presses that wait for the dice to stop rolling.
+    const BUFFER_SIZE: usize = 2;
+    // dragging plans a whole path, so it gets to queue more.
+    const DRAG_BUFFER_SIZE: usize = 32;
+    // how long a key has to be held before it repeats.
+    const REPEAT_DELAY: f64 = 0.25;
+    // states the solver may look at during a frame. a few milliseconds in release.
+    const SOLVER_BUDGET: usize = 5_000;
+
+
     #[derive(Clone, Copy, PartialEq)]
     enum GameState {
         Welcome,
         Ready,
         Moving,
         Done,
+        Daily,
+        LevelSelect,
+        Editor,
+        // the win effect plays, then the game moves on.
+        Won,
     }
 
+    const WIN_DURATION: f64 = 1.5;
+    const FADE_DURATION: f64 = 0.4;
+
 
-    let (mut levels, mut level_index, mut dice) = hot_load();
+    let (mut levels, mut level_index, mut dice) = hot_load().unwrap_or_else(|errors| {
+        for error in errors.iter() {
+            eprintln!("{}", error);
+        }
+        panic!("failed to load levels");
+    });
+    let mut load_errors = vec![];
+    let mut save = load_save();
 
     let mut game_state = GameState::Welcome;
     let mut move_anim = Anim::new(-100.0, 0.125);
+    let mut bump_anim = Anim::new(-100.0, 0.3);
+    let mut bump = (Side::Left, Rejection::Void);
+
+    let mut won_at = 0.0;
+    let mut particles = vec![];
+    let mut fade_in = Anim::new(-100.0, FADE_DURATION);
+
+    let mut hint = None;
+    let mut dead_end = false;
+
+    let mut buffer = std::collections::VecDeque::new();
+    let mut last_press = 0.0;
+    let mut drag_tile = None;
+    let mut dragging = false;
+
+    let mut attempt_level = (usize::MAX, false, false);
+    let mut attempt = Replay::default();
+    let mut attempt_start = 0.0;
+    let mut playback: Option<Playback> = None;
+
+    let mut daily: Option<DailyRun> = None;
+    let mut daily_day = daily::today();
+    let mut daily_result = None;
+    // made when the daily screen opens, so confirming doesn't stall. kept for the day.
+    let mut daily_level: Option<(u64, Level)> = None;
+
+    let mut selected = 0;
+
+    let mut canvas = Canvas::new(IVec2::new(12, 8));
+    // the level the canvas saves over, when it was opened from one.
+    let mut editing: Option<usize> = None;
+    let mut brush = '.';
+    let mut editor_message: Option<String> = None;
+    let mut testing: Option<TestRun> = None;
+
+    let mut input = Input::new();
 
     loop {
         let now = get_time();
+        input.update();
 
         let level = &levels[level_index];
 
-        if game_state == GameState::Ready {
-            let mut moved = false;
-            if is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::A) {
-                moved |= try_move(&mut dice, &level, Side::Left);
+        if (level_index, daily.is_some(), testing.is_some()) != attempt_level {
+            attempt_level = (level_index, daily.is_some(), testing.is_some());
+            attempt = Replay::default();
+            attempt_start = now;
+            playback = None;
+            hint = None;
+            dead_end = false;
+            buffer.clear();
+            last_press = now;
+            drag_tile = None;
+
+            if daily.is_none() && testing.is_none() && (game_state == GameState::Ready || game_state == GameState::Moving) {
+                save.set_last_level(level);
+                write_save(&save);
+            }
+        }
+
+        // presses count while the dice is still rolling, so quick players don't lose moves.
+        if (game_state == GameState::Ready || game_state == GameState::Moving) && playback.is_none() {
+            if let Some(action) = input.pressed_action() {
+                last_press = now;
+                if buffer.len() < BUFFER_SIZE {
+                    buffer.push_back(action);
+                }
+            }
+
+            // macroquad turns touches into mouse input, so this works for fingers too.
+            // a tap has to start with a press, so the click that started the level doesn't count.
+            if is_mouse_button_down(MouseButton::Left) && (drag_tile.is_some() || is_mouse_button_pressed(MouseButton::Left)) {
+                let level = &levels[level_index];
+                let tile = pointer_tile(level);
+                if drag_tile != Some(tile) {
+                    let tap = drag_tile.is_none();
+                    drag_tile = Some(tile);
+
+                    // where the dice ends up after what's queued already.
+                    let mut plan = dice.clone();
+                    for action in buffer.iter() {
+                        action.apply(&mut plan, level);
+                    }
+
+                    // a tap only reaches the neighbours, and goes in even if it bumps.
+                    // a drag has to start at the dice or one of those taps. it fills in tiles
+                    // the pointer skipped, as long as the moves are valid.
+                    if tap {
+                        dragging = tile == plan.pos || is_neighbour(plan.pos, tile);
+                        if let Some(action) = step_towards(&plan, tile).filter(|_| is_neighbour(plan.pos, tile)) {
+                            if buffer.len() < DRAG_BUFFER_SIZE {
+                                buffer.push_back(action);
+                            }
+                        }
+                    }
+                    else if dragging {
+                        while let Some(action) = step_towards(&plan, tile) {
+                            if buffer.len() >= DRAG_BUFFER_SIZE || !action.apply(&mut plan, level) {
+                                break;
+                            }
+                            buffer.push_back(action);
+                        }
+                    }
+                }
             }
-            else if is_key_pressed(KeyCode::Right) || is_key_pressed(KeyCode::D) {
-                moved |= try_move(&mut dice, &level, Side::Right);
+            else {
+                drag_tile = None;
             }
-            else if is_key_pressed(KeyCode::Down) || is_key_pressed(KeyCode::S) {
-                moved |= try_move(&mut dice, &level, Side::Down);
+        }
+
+        if game_state == GameState::Ready {
+            let playing = playback.is_some();
+
+            let action;
+            let mut repeated = false;
+            if let Some(pb) = &mut playback {
+                action = pb.next(now);
+                if pb.done() || input.pressed(Button::Back) {
+                    playback = None;
+                }
             }
-            else if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::W) {
-                moved |= try_move(&mut dice, &level, Side::Up);
+            else {
+                // held keys repeat, once the buffered presses are done.
+                if buffer.is_empty() && now - last_press >= REPEAT_DELAY {
+                    if let Some(action) = input.held_action() {
+                        buffer.push_back(action);
+                        repeated = true;
+                    }
+                }
+                action = buffer.pop_front();
             }
-            else if is_key_pressed(KeyCode::Z) && dice.tail.len() > 0 {
-                dice.undo();
-                moved = true;
+
+            let mut moved = false;
+            if let Some(action) = action {
+                let result = apply(action, &mut dice, level);
+                moved = result.is_ok();
+                if moved && !playing {
+                    attempt.record(action, now - attempt_start);
+                }
+                // the rest was planned from a position the dice isn't in.
+                if !moved {
+                    buffer.clear();
+                }
+                // a held key against a wall waits out the delay again, instead of bumping every frame.
+                if !moved && repeated {
+                    last_press = now;
+                }
+                if let (Err(Some(rejection)), Action::Move(side)) = (result, action) {
+                    bump = (side, rejection);
+                    bump_anim.start = now;
+                }
+            }
+
+
+            // a winning move can land in the same frame, before the state switches to won.
+            if input.pressed(Button::Hint) && !level.detect_win(&dice) {
+                hint = solver::hint(level, &dice, SOLVER_BUDGET).ok();
             }
 
+            // test levels aren't in the pack, so their replays would end up under the wrong name.
+            if testing.is_none() && is_key_pressed(KeyCode::F9) {
+                save_replay(&replay_path(level, &daily, "-unfinished"), &attempt);
+            }
+            if testing.is_none() && is_key_pressed(KeyCode::P) {
+                if let Some(replay) = load_replay(&replay_path(level, &daily, "")) {
+                    set_level(level_index, &levels, &mut level_index, &mut dice);
+                    attempt = Replay::default();
+                    attempt_start = now;
+                    hint = None;
+                    dead_end = false;
+                    playback = Some(Playback { replay, next: 0, start: now });
+                }
+            }
 
             if moved {
                 play_step();
+                hint = None;
+                // levels too large for the solver, or that it gives up on, just don't get the warning.
+                dead_end = solver::can_win(level, &dice, SOLVER_BUDGET) == Ok(false);
 
                 if level.detect_win(&dice) {
-                    if level_index + 1 < levels.len() {
-                        next_level(&levels, &mut level_index, &mut dice);
+                    if !playing && testing.is_none() {
+                        save_replay(&replay_path(level, &daily, ""), &attempt);
+                        if daily.is_none() {
+                            save.complete(level, dice.tail.len() as u32);
+                            write_save(&save);
+                        }
                     }
-                    else {
-                        game_state = GameState::Done;
+
+                    game_state = GameState::Won;
+                    won_at = now;
+                    for goal in level.goals.iter() {
+                        spawn_particles(&mut particles, goal.as_f32() + Vec2::splat(0.5), now);
                     }
                 }
                 else {
                     game_state = GameState::Moving;
-                    move_anim.start = now;
                 }
+                move_anim.start = now;
             }
 
-            if is_key_pressed(KeyCode::R) && dice.tail.len() > 0 {
-                set_level(level_index, &levels, &mut level_index, &mut dice);
-                play_step();
+            if game_state == GameState::Ready && !playing && input.pressed(Button::Back) {
+                if let Some(run) = daily.take() {
+                    end_daily(run, false, now, &mut levels, &mut level_index, &mut dice);
+                    hint = None;
+                    dead_end = false;
+                    daily_result = load_daily_results().into_iter().find(|r| r.day == daily_day);
+                    game_state = GameState::Daily;
+                }
+                else if let Some(run) = testing.take() {
+                    end_test(run, &mut levels, &mut level_index, &mut dice);
+                    editor_message = None;
+                    game_state = GameState::Editor;
+                }
             }
 
-            if is_key_pressed(KeyCode::F1) {
-                prev_level(&levels, &mut level_index, &mut dice);
-            }
-            if is_key_pressed(KeyCode::F2) {
-                next_level(&levels, &mut level_index, &mut dice);
-            }
-            if is_key_pressed(KeyCode::F5) {
-                (levels, level_index, dice) = hot_load();
+            if daily.is_none() && testing.is_none() {
+                if is_key_pressed(KeyCode::F3) {
+                    canvas = Canvas::from_level(&levels[level_index], IVec2::new(12, 8));
+                    editing = Some(level_index);
+                    editor_message = None;
+                    game_state = GameState::Editor;
+                }
+                if is_key_pressed(KeyCode::F1) || is_key_pressed(KeyCode::F2) || is_key_pressed(KeyCode::F5) {
+                    hint = None;
+                    dead_end = false;
+                }
+                if is_key_pressed(KeyCode::F1) {
+                    prev_level(&levels, &mut level_index, &mut dice);
+                }
+                if is_key_pressed(KeyCode::F2) {
+                    next_level(&levels, &mut level_index, &mut dice);
+                }
+                if is_key_pressed(KeyCode::F5) {
+                    match hot_load() {
+                        Ok(loaded) => {
+                            (levels, level_index, dice) = loaded;
+                            load_errors.clear();
+                        }
+                        Err(errors) => load_errors = errors,
+                    }
+                }
             }
         }
         else if game_state == GameState::Moving {
@@ -624,33 +1297,217 @@ async fn main() {
                 game_state = GameState::Ready;
             }
         }
-        else if game_state == GameState::Welcome || game_state == GameState::Done {
+        else if game_state == GameState::Won {
+            if now - won_at >= WIN_DURATION {
+                particles.clear();
+                fade_in.start = now;
+                game_state = GameState::Ready;
+
+                if let Some(run) = daily.take() {
+                    end_daily(run, true, won_at, &mut levels, &mut level_index, &mut dice);
+                    daily_result = load_daily_results().into_iter().find(|r| r.day == daily_day);
+                    game_state = GameState::Daily;
+                }
+                else if let Some(run) = testing.take() {
+                    editor_message = Some(format!("solved in {} moves", dice.tail.len()));
+                    end_test(run, &mut levels, &mut level_index, &mut dice);
+                    game_state = GameState::Editor;
+                }
+                else if level_index + 1 < levels.len() {
+                    next_level(&levels, &mut level_index, &mut dice);
+                }
+                else {
+                    game_state = GameState::Done;
+                }
+            }
+        }
+        else if (game_state == GameState::Welcome || game_state == GameState::Done)
+            && (input.pressed(Button::Confirm) || is_mouse_button_pressed(MouseButton::Left))
+        {
+            // welcome picks up where the last session stopped. done starts over.
+            let index = match game_state {
+                GameState::Welcome => save.last_level_index(&levels).unwrap_or(0),
+                _ => 0,
+            };
+            set_level(index, &levels, &mut level_index, &mut dice);
+            // a new attempt, even when it's the level that was played last.
+            attempt_level = (usize::MAX, false, false);
+            game_state = GameState::Ready;
+            play_goal();
+        }
+        else if game_state == GameState::Welcome && is_key_pressed(KeyCode::D) {
+            daily_day = daily::today();
+            daily_result = load_daily_results().into_iter().find(|r| r.day == daily_day);
+            if daily_level.as_ref().map(|(day, _)| *day) != Some(daily_day) {
+                daily_level = Some((daily_day, daily::level(daily_day)));
+            }
+            game_state = GameState::Daily;
+        }
+        else if game_state == GameState::Welcome && is_key_pressed(KeyCode::L) {
+            selected = save.last_level_index(&levels).unwrap_or(0);
+            game_state = GameState::LevelSelect;
+        }
+        else if game_state == GameState::Welcome && is_key_pressed(KeyCode::E) {
+            editor_message = None;
+            game_state = GameState::Editor;
+        }
+        else if game_state == GameState::Editor {
+            let control = is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl);
+
+            let brushes = [
+                (KeyCode::Key1, '1'), (KeyCode::Key2, '2'), (KeyCode::Key3, '3'),
+                (KeyCode::Key4, '4'), (KeyCode::Key5, '5'), (KeyCode::Key6, '6'),
+                (KeyCode::Period, '.'), (KeyCode::Space, ' '),
+            ];
+            for (key, tile) in brushes {
+                if is_key_pressed(key) {
+                    brush = tile;
+                }
+            }
+            if !control && is_key_pressed(KeyCode::S) {
+                brush = 's';
+            }
+
+            let (origin, tile_size) = board_layout(canvas.size);
+            let cell = ((Vec2::from(mouse_position()) - origin) / tile_size).floor().as_i32();
+            let painted =
+                   (is_mouse_button_down(MouseButton::Left)  && canvas.paint(cell, brush))
+                || (is_mouse_button_down(MouseButton::Right) && canvas.paint(cell, ' '));
+            if painted {
+                editor_message = None;
+            }
+
             if is_key_pressed(KeyCode::Enter) {
+                match canvas.to_level() {
+                    Ok(level) if level.goals.is_empty() => {
+                        editor_message = Some("add a goal to play".to_string());
+                    }
+                    Ok(level) => {
+                        let pack = std::mem::replace(&mut levels, vec![level]);
+                        testing = Some(TestRun { pack, pack_index: level_index });
+                        set_level(0, &levels, &mut level_index, &mut dice);
+                        game_state = GameState::Ready;
+                    }
+                    Err(errors) => editor_message = Some(errors[0].to_string()),
+                }
+            }
+            else if control && is_key_pressed(KeyCode::S) {
+                editor_message = Some(match canvas.to_level().map(|level| save_level(&level, editing)) {
+                    Ok(Ok((path, index))) => {
+                        editing = Some(index);
+                        // straight into the pack, like pressing F5.
+                        if let Ok((loaded, _, _)) = hot_load() {
+                            levels = loaded;
+                            set_level(index.min(levels.len() - 1), &levels, &mut level_index, &mut dice);
+                        }
+                        format!("saved as level {} in {}", index, path.display())
+                    }
+                    Ok(Err(error)) => format!("couldn't save: {}", error),
+                    Err(errors) => errors[0].to_string(),
+                });
+            }
+            else if is_key_pressed(KeyCode::Escape) {
+                game_state = GameState::Welcome;
+            }
+        }
+        else if game_state == GameState::LevelSelect {
+            let columns = level_select_columns(levels.len());
+            if input.pressed(Button::Left) {
+                selected = selected.saturating_sub(1);
+            }
+            if input.pressed(Button::Right) {
+                selected = (selected + 1).min(levels.len() - 1);
+            }
+            if input.pressed(Button::Up) && selected >= columns {
+                selected -= columns;
+            }
+            if input.pressed(Button::Down) && selected + columns < levels.len() {
+                selected += columns;
+            }
+
+            let mut play = input.pressed(Button::Confirm);
+            if is_mouse_button_pressed(MouseButton::Left) {
+                let mouse = Vec2::from(mouse_position());
+                if let Some(i) = (0..levels.len()).find(|i| level_select_cell(*i, levels.len()).contains(mouse)) {
+                    selected = i;
+                    play = true;
+                }
+            }
+
+            if play {
+                set_level(selected, &levels, &mut level_index, &mut dice);
+                attempt_level = (usize::MAX, false, false);
+                game_state = GameState::Ready;
+                play_goal();
+            }
+            else if input.pressed(Button::Back) {
+                game_state = GameState::Welcome;
+            }
+        }
+        else if game_state == GameState::Daily {
+            if input.pressed(Button::Confirm) {
+                let level = match &daily_level {
+                    Some((day, level)) if *day == daily_day => level.clone(),
+                    _ => daily::level(daily_day),
+                };
+                let pack = std::mem::replace(&mut levels, vec![level]);
+                daily = Some(DailyRun { day: daily_day, start: now, pack, pack_index: level_index });
                 set_level(0, &levels, &mut level_index, &mut dice);
                 game_state = GameState::Ready;
                 play_goal();
             }
+            else if input.pressed(Button::Back) {
+                game_state = GameState::Welcome;
+            }
         }
 
 
-        if game_state == GameState::Ready || game_state == GameState::Moving {
+        if game_state == GameState::Ready || game_state == GameState::Moving || game_state == GameState::Won {
             let level = &levels[level_index];
-
-            let sx = screen_width()  / level.size.x as f32;
-            let sy = screen_height() / level.size.y as f32;
-            let s = (sx.min(sy) / 1.5).floor().min(150.0);
-            let tile_size = Vec2::splat(s);
-
-            let board_size = level.size.as_f32() * tile_size;
-            let screen_size = Vec2::new(screen_width(), screen_height());
-            let origin = (screen_size/2.0 - board_size/2.0).floor();
+            let (origin, tile_size) = board_layout(level.size);
 
             draw_background(origin, tile_size);
 
             let t = move_anim.t();
             level.render(origin, tile_size, t);
-            draw_moves(&level, &dice, origin, tile_size);
-            dice.render(origin, tile_size, &level, t);
+            let hint_side = match hint {
+                Some(solver::Hint::Move(side)) => Some(side),
+                Some(solver::Hint::Undo(_)) => dice.tail.last().map(|(pos, _)| Side::from_unit(*pos - dice.pos)),
+                _ => None,
+            };
+            draw_moves(level, &dice, origin, tile_size, hint_side);
+
+            let b = bump_anim.t();
+            let mut shake = Vec2::ZERO;
+            if b < 1.0 {
+                let (side, rejection) = bump;
+                draw_bump(origin + (dice.pos + side.unit()).as_f32()*tile_size, tile_size, b, rejection);
+                let s = (b * 4.0 * std::f32::consts::PI).sin() * (1.0 - b) * 0.08;
+                shake = s * side.unit().as_f32() * tile_size;
+            }
+            dice.render(origin + shake, tile_size, level, t);
+
+            if game_state == GameState::Won {
+                draw_win(&dice, origin, tile_size, (now - won_at) as f32);
+                particles.retain(|particle: &Particle| particle.alive(now));
+                for particle in particles.iter() {
+                    particle.draw(origin, tile_size, now);
+                }
+            }
+            if dead_end && t == 1.0 {
+                draw_dead_end(origin + dice.pos.as_f32()*tile_size, tile_size);
+            }
+
+            draw_hud(level, &dice);
+            if let Some(hint) = hint {
+                draw_hint(hint);
+            }
+            draw_errors(&load_errors);
+
+            if game_state == GameState::Won {
+                let fade = (now - won_at - (WIN_DURATION - FADE_DURATION)) / FADE_DURATION;
+                draw_fade(fade.clamp(0.0, 1.0) as f32);
+            }
         }
 
         if game_state == GameState::Welcome || game_state == GameState::Done {
@@ -671,8 +1528,28 @@ async fn main() {
                 dest_size: Some(size),
                 .. Default::default()
             });
+
+            if game_state == GameState::Welcome {
+                draw_menu(&["enter or click - play", "l - level select", "d - daily puzzle", "e - level editor"]);
+            }
+        }
+
+        if game_state == GameState::Daily {
+            draw_background(Vec2::ZERO, Vec2::splat(150.0));
+            draw_daily(daily_day, daily_result.as_ref());
         }
 
+        if game_state == GameState::LevelSelect {
+            draw_background(Vec2::ZERO, Vec2::splat(150.0));
+            draw_level_select(&levels, &save, selected);
+        }
+
+        if game_state == GameState::Editor {
+            draw_editor(&canvas, brush, editor_message.as_deref());
+        }
+
+        draw_fade(1.0 - fade_in.t());
+
         next_frame().await;
     }
 }
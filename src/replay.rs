@@ -0,0 +1,99 @@
+use crate::{Level, Dice, Side, try_move};
+
+
+// what a player can do to the dice.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Move(Side),
+    Undo,
+    Redo,
+    Restart,
+}
+
+impl Action {
+    // returns whether anything changed.
+    pub fn apply(self, dice: &mut Dice, level: &Level) -> bool {
+        match self {
+            Action::Move(side) => try_move(dice, level, side).is_ok(),
+
+            Action::Undo    => dice.undo(),
+            Action::Redo    => dice.redo(),
+            Action::Restart => dice.restart(level.start),
+        }
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Event {
+    pub action: Action,
+    // seconds since the attempt started.
+    pub time: Option<f64>,
+}
+
+// one attempt at a level.
+// written as events separated by whitespace, like `R@0.52 D@0.91 Z@1.40 X@2.00`.
+// moves are `L R D U`, `Z` is undo, `Y` is redo and `X` is restart.
+// the `@seconds` part is optional, so a plain solution like `RRDLU` plays back too.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Replay {
+    pub events: Vec<Event>,
+}
+
+impl Replay {
+    pub fn record(&mut self, action: Action, time: f64) {
+        self.events.push(Event { action, time: Some(time) });
+    }
+
+    pub fn parse(text: &str) -> Result<Replay, String> {
+        let mut events = vec![];
+        for token in text.split_whitespace() {
+            let (actions, time) = match token.split_once('@') {
+                Some((actions, time)) => {
+                    let time = time.parse().map_err(|_| format!("bad time in {:?}", token))?;
+                    (actions, Some(time))
+                }
+                None => (token, None),
+            };
+
+            for c in actions.chars() {
+                let action = match c {
+                    'Z' => Action::Undo,
+                    'Y' => Action::Redo,
+                    'X' => Action::Restart,
+                    _ => Action::Move(Side::from_char(c).ok_or_else(|| format!("unknown action {:?}", c))?),
+                };
+                events.push(Event { action, time: None });
+            }
+
+            // the time belongs to the last action of the token.
+            if let Some(event) = events.last_mut() {
+                event.time = time;
+            }
+        }
+        Ok(Replay { events })
+    }
+}
+
+impl std::fmt::Display for Replay {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (i, event) in self.events.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+
+            let c = match event.action {
+                Action::Move(side) => side.to_char(),
+                Action::Undo => 'Z',
+                Action::Redo => 'Y',
+                Action::Restart => 'X',
+            };
+            write!(f, "{}", c)?;
+
+            if let Some(time) = event.time {
+                write!(f, "@{:.2}", time)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+
+use crate::Level;
+
+
+// progress through the levels, keyed by `Level::content_hash`.
+// a level with a best move count has been completed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Save {
+    pub best: BTreeMap<u64, u32>,
+    pub last_level: Option<u64>,
+}
+
+impl Save {
+    // one entry per line: `last <hash>` or `best <hash> <moves>`, hashes in hex.
+    // lines that don't parse are skipped, so an old or damaged save still loads.
+    pub fn parse(text: &str) -> Save {
+        let mut save = Save::default();
+        for line in text.lines() {
+            let mut parts = line.split_whitespace();
+            let hash = |part: Option<&str>| u64::from_str_radix(part?, 16).ok();
+            match parts.next() {
+                Some("last") => {
+                    if let Some(hash) = hash(parts.next()) {
+                        save.last_level = Some(hash);
+                    }
+                }
+                Some("best") => {
+                    let Some(hash) = hash(parts.next()) else {
+                        continue;
+                    };
+                    if let Some(Ok(moves)) = parts.next().map(str::parse) {
+                        save.best.insert(hash, moves);
+                    }
+                }
+                _ => {}
+            }
+        }
+        save
+    }
+
+    pub fn is_completed(&self, level: &Level) -> bool {
+        self.best.contains_key(&level.content_hash())
+    }
+
+    pub fn best_moves(&self, level: &Level) -> Option<u32> {
+        self.best.get(&level.content_hash()).copied()
+    }
+
+    // keeps the fewest moves.
+    pub fn complete(&mut self, level: &Level, moves: u32) {
+        let best = self.best.entry(level.content_hash()).or_insert(moves);
+        *best = (*best).min(moves);
+    }
+
+    pub fn set_last_level(&mut self, level: &Level) {
+        self.last_level = Some(level.content_hash());
+    }
+
+    // where the last level played is in `levels`, if it's still there.
+    pub fn last_level_index(&self, levels: &[Level]) -> Option<usize> {
+        let last = self.last_level?;
+        levels.iter().position(|level| level.content_hash() == last)
+    }
+}
+
+impl std::fmt::Display for Save {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if let Some(last) = self.last_level {
+            writeln!(f, "last {:016x}", last)?;
+        }
+        for (hash, moves) in self.best.iter() {
+            writeln!(f, "best {:016x} {}", hash, moves)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::{HashMap, HashSet};
+
+use glam::IVec2;
+
+use crate::{Level, Dice, Side, Orientation, MOVES, try_move};
+
+
+// breadth first search over dice states.
+// only the tiles covered by the tail matter for where a dice can still go,
+// not the order they were covered in. so states are deduped on that set.
+// undoing never reaches anything new, the solver only moves forward.
+
+pub const MAX_TILES: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SolveError {
+    // more than `MAX_TILES` tiles.
+    TooLarge,
+    // gave up after looking at the given number of states.
+    OutOfBudget,
+}
+
+impl std::fmt::Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SolveError::TooLarge => write!(f, "too large to solve, the solver takes up to {} tiles", MAX_TILES),
+            SolveError::OutOfBudget => write!(f, "gave up searching"),
+        }
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash)]
+struct TileSet([u64; MAX_TILES / 64]);
+
+impl TileSet {
+    fn has(&self, index: usize) -> bool {
+        self.0[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    fn insert(&mut self, index: usize) {
+        self.0[index / 64] |= 1 << (index % 64);
+    }
+}
+
+// a dice, as far as the rules care.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct State {
+    pos: u16,
+    orientation: Orientation,
+    tail: TileSet,
+}
+
+fn tile_index(level: &Level, pos: IVec2) -> usize {
+    (pos.y*level.size.x + pos.x) as usize
+}
+
+impl State {
+    pub fn new(level: &Level, dice: &Dice) -> Result<State, SolveError> {
+        if (level.size.x * level.size.y) as usize > MAX_TILES {
+            return Err(SolveError::TooLarge);
+        }
+
+        let mut tail = TileSet::default();
+        for (pos, _) in dice.tail.iter() {
+            tail.insert(tile_index(level, *pos));
+        }
+        Ok(State { pos: tile_index(level, dice.pos) as u16, orientation: dice.orientation(), tail })
+    }
+
+    pub fn pos(&self, level: &Level) -> IVec2 {
+        let pos = self.pos as i32;
+        IVec2::new(pos % level.size.x, pos / level.size.x)
+    }
+
+    // the same rules as `try_move`, minus undoing.
+    pub fn step(self, level: &Level, side: Side) -> Option<State> {
+        let target = self.pos(level) + side.unit();
+        if !level.can_enter(target, self.orientation.get(side)) {
+            return None;
+        }
+
+        let index = tile_index(level, target);
+        if self.tail.has(index) {
+            return None;
+        }
+
+        let mut tail = self.tail;
+        tail.insert(self.pos as usize);
+        Some(State { pos: index as u16, orientation: self.orientation.roll(side), tail })
+    }
+
+    pub fn is_win(self, level: &Level) -> bool {
+        level.goals.iter().all(|goal| {
+            let index = tile_index(level, *goal);
+            self.tail.has(index) || self.pos as usize == index
+        })
+    }
+
+    fn flood(self, level: &Level) -> TileSet {
+        let mut reached = TileSet::default();
+        reached.insert(self.pos as usize);
+
+        let mut stack = vec![self.pos(level)];
+        while let Some(pos) = stack.pop() {
+            for side in MOVES {
+                let next = pos + side.unit();
+                if level.get(next.x, next.y) == ' ' {
+                    continue;
+                }
+
+                let index = tile_index(level, next);
+                if !self.tail.has(index) && !reached.has(index) {
+                    reached.insert(index);
+                    stack.push(next);
+                }
+            }
+        }
+
+        reached
+    }
+
+    // can every goal that isn't covered yet still be walked to?
+    // ignores the faces, so this only ever rules states out.
+    pub fn goals_reachable(self, level: &Level) -> bool {
+        let reached = self.flood(level);
+        level.goals.iter().all(|goal| {
+            let index = tile_index(level, *goal);
+            self.tail.has(index) || reached.has(index)
+        })
+    }
+}
+
+// the tiles a dice could walk to, ignoring faces.
+// indexed like the level's tiles. unlike the search, this works at any size.
+pub fn flood(level: &Level, dice: &Dice) -> Vec<bool> {
+    let mut reached = vec![false; (level.size.x * level.size.y) as usize];
+    reached[tile_index(level, dice.pos)] = true;
+
+    let mut stack = vec![dice.pos];
+    while let Some(pos) = stack.pop() {
+        for side in MOVES {
+            let next = pos + side.unit();
+            if level.get(next.x, next.y) == ' ' || dice.on_tail(next) {
+                continue;
+            }
+
+            let index = tile_index(level, next);
+            if !reached[index] {
+                reached[index] = true;
+                stack.push(next);
+            }
+        }
+    }
+
+    reached
+}
+
+pub fn goals_reachable(level: &Level, dice: &Dice) -> Result<bool, SolveError> {
+    Ok(State::new(level, dice)?.goals_reachable(level))
+}
+
+
+pub fn solve(level: &Level) -> Result<Option<Vec<Side>>, SolveError> {
+    solve_from(level, &Dice::new(level.start))
+}
+
+// like `solve`, but gives up after `budget` states.
+pub fn solve_within(level: &Level, mut budget: usize) -> Result<Option<Vec<Side>>, SolveError> {
+    search(level, &Dice::new(level.start), &mut budget)
+}
+
+// shortest sequence of moves that wins from `dice`, keeping its tail.
+pub fn solve_from(level: &Level, dice: &Dice) -> Result<Option<Vec<Side>>, SolveError> {
+    let mut budget = usize::MAX;
+    search(level, dice, &mut budget)
+}
+
+// every state looked at takes one from `budget`.
+fn search(level: &Level, dice: &Dice, budget: &mut usize) -> Result<Option<Vec<Side>>, SolveError> {
+    let start = State::new(level, dice)?;
+    if start.is_win(level) {
+        return Ok(Some(vec![]));
+    }
+
+    struct Node {
+        state: State,
+        parent: usize,
+        side: Side,
+    }
+
+    let mut nodes = vec![Node { state: start, parent: usize::MAX, side: Side::Sky }];
+    let mut seen = HashSet::new();
+    seen.insert(start);
+
+    let mut i = 0;
+    while i < nodes.len() {
+        if *budget == 0 {
+            return Err(SolveError::OutOfBudget);
+        }
+        *budget -= 1;
+
+        for side in MOVES {
+            let Some(next) = nodes[i].state.step(level, side) else {
+                continue;
+            };
+
+            if next.is_win(level) {
+                let mut moves = vec![side];
+                let mut at = i;
+                while nodes[at].parent != usize::MAX {
+                    moves.push(nodes[at].side);
+                    at = nodes[at].parent;
+                }
+                moves.reverse();
+                return Ok(Some(moves));
+            }
+
+            if !next.goals_reachable(level) || !seen.insert(next) {
+                continue;
+            }
+
+            nodes.push(Node { state: next, parent: i, side });
+        }
+
+        i += 1;
+    }
+
+    Ok(None)
+}
+
+// the cheap flood fill first, the full search only if that passes.
+// the search gives up after `budget` states, open levels can have millions.
+pub fn can_win(level: &Level, dice: &Dice, mut budget: usize) -> Result<bool, SolveError> {
+    Ok(level.detect_win(dice) || (goals_reachable(level, dice)? && search(level, dice, &mut budget)?.is_some()))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hint {
+    Move(Side),
+    // no win from here. undo this many moves to get back to a state that can win.
+    Undo(usize),
+    // not even the start can win.
+    Impossible,
+    // nothing left to do.
+    Won,
+}
+
+// `budget` is shared by the searches for every undo step.
+pub fn hint(level: &Level, dice: &Dice, mut budget: usize) -> Result<Hint, SolveError> {
+    if level.detect_win(dice) {
+        return Ok(Hint::Won);
+    }
+    if let Some(solution) = search(level, dice, &mut budget)? {
+        return Ok(solution.first().map_or(Hint::Won, |side| Hint::Move(*side)));
+    }
+
+    let mut dice = dice.clone();
+    for undos in 1..=dice.tail.len() {
+        dice.undo();
+        if goals_reachable(level, &dice)? && search(level, &dice, &mut budget)?.is_some() {
+            return Ok(Hint::Undo(undos));
+        }
+    }
+    Ok(Hint::Impossible)
+}
+
+// plays `moves` from the start and checks that they win.
+pub fn check_solution(level: &Level, moves: &[Side]) -> bool {
+    let mut dice = Dice::new(level.start);
+    for side in moves {
+        if try_move(&mut dice, level, *side).is_err() {
+            return false;
+        }
+    }
+    level.detect_win(&dice)
+}
+
+
+// SOLUTION COUNTING
+
+// a point where the solutions split up.
+// `options` holds each move that still wins, with its solution count.
+pub struct Branch {
+    pub prefix: Vec<Side>,
+    pub options: Vec<(Side, u64)>,
+}
+
+pub struct Analysis {
+    // saturates at the cap.
+    pub solutions: u64,
+    pub branches: Vec<Branch>,
+}
+
+impl Analysis {
+    pub fn is_unique(&self) -> bool {
+        self.solutions == 1
+    }
+}
+
+// counts the distinct move sequences that win the level (up to `cap`)
+// and finds the states where more than one move leads to a win.
+// like in the game, a sequence ends as soon as it wins.
+// gives up after counting from `budget` states.
+pub fn analyze(level: &Level, cap: u64, budget: usize) -> Result<Analysis, SolveError> {
+    let mut counter = Counter { level, cap, budget, memo: HashMap::new() };
+
+    let start = State::new(level, &Dice::new(level.start))?;
+    let solutions = counter.count(start)?;
+
+    let mut branches = vec![];
+    let mut seen = HashSet::new();
+    if solutions > 0 {
+        counter.branches(start, &mut vec![], &mut seen, &mut branches)?;
+    }
+
+    Ok(Analysis { solutions, branches })
+}
+
+struct Counter<'a> {
+    level: &'a Level,
+    cap: u64,
+    budget: usize,
+    memo: HashMap<State, u64>,
+}
+
+impl<'a> Counter<'a> {
+    fn count(&mut self, state: State) -> Result<u64, SolveError> {
+        if state.is_win(self.level) {
+            return Ok(1);
+        }
+        if !state.goals_reachable(self.level) {
+            return Ok(0);
+        }
+
+        if let Some(count) = self.memo.get(&state) {
+            return Ok(*count);
+        }
+
+        if self.budget == 0 {
+            return Err(SolveError::OutOfBudget);
+        }
+        self.budget -= 1;
+
+        let mut count = 0;
+        for side in MOVES {
+            if let Some(next) = state.step(self.level, side) {
+                count = (count + self.count(next)?).min(self.cap);
+            }
+
+            // past the cap, the exact count doesn't matter.
+            if count == self.cap {
+                break;
+            }
+        }
+
+        self.memo.insert(state, count);
+        Ok(count)
+    }
+
+    fn branches(&mut self, state: State, prefix: &mut Vec<Side>, seen: &mut HashSet<State>, out: &mut Vec<Branch>) -> Result<(), SolveError> {
+        if !seen.insert(state) {
+            return Ok(());
+        }
+
+        let mut options = vec![];
+        for side in MOVES {
+            if let Some(next) = state.step(self.level, side) {
+                let count = self.count(next)?;
+                if count > 0 {
+                    options.push((side, next, count));
+                }
+            }
+        }
+
+        if options.len() > 1 {
+            let options = options.iter().map(|(side, _, count)| (*side, *count)).collect();
+            out.push(Branch { prefix: prefix.clone(), options });
+        }
+
+        for (side, next, _) in options {
+            if !next.is_win(self.level) {
+                prefix.push(side);
+                self.branches(next, prefix, seen, out)?;
+                prefix.pop();
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,112 @@
+use glam::IVec2;
+use proptest::prelude::*;
+
+use jam_gmtk_2022::*;
+
+
+const SIDES: [Side; 6] = [Side::Floor, Side::Sky, Side::Left, Side::Right, Side::Down, Side::Up];
+
+fn faces(dice: &Dice) -> [u8; 6] {
+    SIDES.map(|side| dice.get(side))
+}
+
+fn opposite(side: Side) -> Side {
+    Side::from_unit(-side.unit())
+}
+
+fn opposite_face(side: Side) -> Side {
+    match side {
+        Side::Floor => Side::Sky,
+        Side::Sky   => Side::Floor,
+        _ => opposite(side),
+    }
+}
+
+fn side() -> impl Strategy<Value = Side> {
+    prop_oneof![Just(Side::Left), Just(Side::Right), Just(Side::Down), Just(Side::Up)]
+}
+
+// rolls on an endless floor, so any sequence of moves is fine.
+fn rolled(moves: &[Side]) -> Dice {
+    let mut dice = Dice::new(IVec2::ZERO);
+    for side in moves {
+        dice.move_thyself(*side);
+    }
+    dice
+}
+
+
+proptest! {
+    #[test]
+    fn opposite_faces_sum_to_seven(moves in prop::collection::vec(side(), 0..64)) {
+        let dice = rolled(&moves);
+        for side in SIDES {
+            prop_assert_eq!(dice.get(side) + dice.get(opposite_face(side)), 7);
+        }
+    }
+
+    #[test]
+    fn rolling_back_restores_orientation(moves in prop::collection::vec(side(), 0..64), side in side()) {
+        let mut dice = rolled(&moves);
+        let before = faces(&dice);
+
+        dice.move_thyself(side);
+        dice.move_thyself(opposite(side));
+        prop_assert_eq!(faces(&dice), before);
+    }
+
+    #[test]
+    fn four_rolls_are_the_identity(moves in prop::collection::vec(side(), 0..64), side in side()) {
+        let mut dice = rolled(&moves);
+        let before = faces(&dice);
+
+        for _ in 0..4 {
+            dice.move_thyself(side);
+        }
+        prop_assert_eq!(faces(&dice), before);
+    }
+
+    #[test]
+    fn undo_reverses_move(moves in prop::collection::vec(side(), 0..64), side in side()) {
+        let mut dice = rolled(&moves);
+        let (pos, before, tail) = (dice.pos, faces(&dice), dice.tail.clone());
+
+        dice.move_thyself(side);
+        dice.undo();
+        prop_assert_eq!(dice.pos, pos);
+        prop_assert_eq!(faces(&dice), before);
+        prop_assert_eq!(dice.tail, tail);
+    }
+
+    #[test]
+    fn redo_reverses_undos(moves in prop::collection::vec(side(), 1..64), undos in 1..64usize) {
+        let mut dice = rolled(&moves);
+        let (pos, before, tail) = (dice.pos, faces(&dice), dice.tail.clone());
+
+        let undos = undos.min(moves.len());
+        for _ in 0..undos {
+            dice.undo();
+        }
+        for _ in 0..undos {
+            prop_assert!(dice.redo());
+        }
+        prop_assert!(!dice.redo());
+        prop_assert_eq!(dice.pos, pos);
+        prop_assert_eq!(faces(&dice), before);
+        prop_assert_eq!(dice.tail, tail);
+    }
+}
+
+#[test]
+fn orientations_are_distinct() {
+    let mut seen = std::collections::HashSet::new();
+    for index in 0..Orientation::COUNT as u8 {
+        let orientation = Orientation::from_index(index);
+        seen.insert(SIDES.map(|side| orientation.get(side)));
+
+        for side in [Side::Left, Side::Right, Side::Down, Side::Up] {
+            assert_eq!(orientation.roll(side).roll(opposite(side)), orientation);
+        }
+    }
+    assert_eq!(seen.len(), Orientation::COUNT);
+}
@@ -0,0 +1,76 @@
+use glam::IVec2;
+
+use jam_gmtk_2022::*;
+use jam_gmtk_2022::editor::Canvas;
+
+
+#[test]
+fn the_start_moves_but_stays_unique() {
+    let mut canvas = Canvas::new(IVec2::new(5, 3));
+    let start = canvas.start();
+    assert_eq!(start, IVec2::new(2, 1));
+
+    assert!(!canvas.paint(start, ' '));
+    assert!(canvas.paint(IVec2::new(0, 0), 's'));
+    assert_eq!(canvas.start(), IVec2::new(0, 0));
+    assert_eq!(canvas.get(start), '.');
+
+    assert!(!canvas.paint(IVec2::new(5, 0), '.'));
+    assert!(!canvas.paint(IVec2::new(1, 0), 'x'));
+}
+
+#[test]
+fn levels_are_cropped_to_the_painted_tiles() {
+    let mut canvas = Canvas::new(IVec2::new(8, 8));
+    canvas.paint(IVec2::new(5, 4), '.');
+    canvas.paint(IVec2::new(5, 5), '3');
+
+    assert_eq!(canvas.rows(), ["s.", " 3"]);
+    let level = canvas.to_level().unwrap();
+    assert_eq!(level.start, IVec2::ZERO);
+    assert_eq!(level.goals, [IVec2::new(1, 1)]);
+    assert_eq!(canvas.to_uncropped_level().size, canvas.size);
+}
+
+#[test]
+fn levels_edit_and_save_back() {
+    let level = &parse_levels(include_str!("../src/levels.txt")).unwrap()[3];
+    let canvas = Canvas::from_level(level, IVec2::new(12, 8));
+    let edited = canvas.to_level().unwrap();
+    assert_eq!(edited.content_hash(), level.content_hash());
+
+    // appended levels end in an empty line.
+    let file = format!("{}\n\n{}\n\n", edited, edited);
+    assert_eq!(parse_levels(&file).unwrap().len(), 2);
+}
+
+#[test]
+fn edited_levels_keep_their_headers_but_not_par() {
+    let level = Level::parse(&["name: ring", "author: me", "par: 3", "hint: go round", "s.1"]).unwrap();
+    let canvas = Canvas::from_level(&level, IVec2::new(8, 8));
+    let edited = canvas.to_level().unwrap();
+
+    assert_eq!(edited.meta.name.as_deref(), Some("ring"));
+    assert_eq!(edited.meta.author.as_deref(), Some("me"));
+    assert_eq!(edited.meta.hint.as_deref(), Some("go round"));
+    assert_eq!(edited.meta.par, None);
+}
+
+#[test]
+fn saving_replaces_the_level_in_place() {
+    let file = "s.1\n\nbegin\nname: two\ns..\n\n..2\nend\n\ns.3\n";
+    let level = Level::parse(&["name: new", "s..", "..4"]).unwrap();
+
+    let replaced = replace_level(file, 1, &level).unwrap();
+    assert_eq!(replaced, "s.1\n\nname: new\ns..\n..4\n\ns.3\n");
+    let levels = parse_levels(&replaced).unwrap();
+    assert_eq!(levels.len(), 3);
+    assert_eq!(levels[1].content_hash(), level.content_hash());
+
+    assert_eq!(replace_level(file, 3, &level), None);
+
+    let appended = parse_levels(&append_level(file, &level)).unwrap();
+    assert_eq!(appended.len(), 4);
+    assert_eq!(appended[3].content_hash(), level.content_hash());
+    assert_eq!(append_level("", &level), format!("{}\n", level));
+}
@@ -0,0 +1,65 @@
+use jam_gmtk_2022::*;
+use jam_gmtk_2022::generator::{self, Rng, Settings};
+
+
+#[test]
+fn generated_levels_have_one_solution() {
+    let settings = Settings::default();
+    let mut rng = Rng::new(2022);
+    for _ in 0..5 {
+        let level = generator::generate(&mut rng, &settings).unwrap();
+        let solution = solver::solve(&level).unwrap().unwrap();
+        assert!(solution.len() >= settings.min_moves);
+        assert_eq!(level.meta.par, Some(solution.len() as u32));
+        assert!(solver::analyze(&level, 2, usize::MAX).unwrap().is_unique());
+    }
+}
+
+#[test]
+fn same_seed_same_levels() {
+    let settings = Settings::default();
+    let a = generator::generate(&mut Rng::new(7), &settings).unwrap();
+    let b = generator::generate(&mut Rng::new(7), &settings).unwrap();
+    assert_eq!(a.to_string(), b.to_string());
+}
+
+#[test]
+fn written_levels_parse_back() {
+    let mut rng = Rng::new(99);
+    let text = (0..3)
+        .map(|_| generator::generate(&mut rng, &Settings::default()).unwrap().to_string())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let levels = parse_levels(&text).unwrap();
+    assert_eq!(levels.len(), 3);
+    assert_eq!(levels.iter().map(|level| level.to_string()).collect::<Vec<_>>().join("\n\n"), text);
+}
+
+#[test]
+fn daily_dates() {
+    assert_eq!(daily::date(0), "1970-01-01");
+    assert_eq!(daily::date(19200), "2022-07-27");
+    assert_eq!(daily::date(11016), "2000-02-29");
+}
+
+#[test]
+fn daily_results_round_trip() {
+    let mut results = vec![];
+    daily::record(&mut results, daily::DailyResult { day: 5, solved: false, moves: 9, seconds: 12.0 });
+    daily::record(&mut results, daily::DailyResult { day: 5, solved: true, moves: 20, seconds: 40.5 });
+    daily::record(&mut results, daily::DailyResult { day: 5, solved: true, moves: 24, seconds: 30.0 });
+    daily::record(&mut results, daily::DailyResult { day: 6, solved: true, moves: 18, seconds: 20.25 });
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].moves, 20);
+    assert_eq!(daily::parse_results(&daily::write_results(&results)), results);
+}
+
+#[test]
+fn daily_levels_are_solvable() {
+    // a day that used to take seconds to generate in debug builds.
+    let level = daily::level(20379);
+    assert!(solver::solve(&level).unwrap().is_some());
+    assert_eq!(level.meta.name.as_deref(), Some("daily 2025-10-18"));
+}
@@ -0,0 +1,198 @@
+use jam_gmtk_2022::*;
+
+
+// one shortest solution per level in src/levels.txt.
+// update these when changing the levels.
+const SOLUTIONS: &[&str] = &[
+    "RRR",
+    "RRRDDRRR",
+    "RRRDDRRUR",
+    "RRDRU",
+    "RRDRU",
+    "DRURRDRU",
+    "UUURRRURRRDRRUUU",
+    "UUURUUURRRDDRRDRRUUU",
+    "UUURUUURRRDDLDDDRRRRUURUUU",
+    "UUURRRDDRRRRUULULLUULLLD",
+    "UUUUURRRRRRDDLLLLDDRRRRRRUUUUU",
+    "URRRRUURRUULLLLDDLLUUU",
+];
+
+fn levels() -> Vec<Level> {
+    match parse_levels(include_str!("../src/levels.txt")) {
+        Ok(levels) => levels,
+        Err(errors) => {
+            for error in errors.iter() {
+                eprintln!("{}", error);
+            }
+            panic!("src/levels.txt doesn't parse");
+        }
+    }
+}
+
+
+#[test]
+fn every_level_has_a_reference_solution() {
+    assert_eq!(levels().len(), SOLUTIONS.len());
+}
+
+#[test]
+fn reference_solutions_win() {
+    for (i, (level, solution)) in levels().iter().zip(SOLUTIONS).enumerate() {
+        let moves = parse_moves(solution).unwrap();
+
+        // the game moves on as soon as a level is won,
+        // so the win has to happen on the last move, not before.
+        let mut dice = Dice::new(level.start);
+        for (j, side) in moves.iter().enumerate() {
+            assert!(!level.detect_win(&dice), "level {} won early, after {} moves", i, j);
+            assert!(try_move(&mut dice, level, *side).is_ok(), "level {}: move {} ({:?}) rejected", i, j, side);
+        }
+        assert!(level.detect_win(&dice), "level {}: {} doesn't win", i, solution);
+    }
+}
+
+#[test]
+fn every_level_is_solvable() {
+    for (i, (level, reference)) in levels().iter().zip(SOLUTIONS).enumerate() {
+        let solution = solver::solve(level).unwrap().unwrap_or_else(|| panic!("level {} has no solution", i));
+        assert!(solver::check_solution(level, &solution), "level {}: solver found {}, which doesn't win", i, moves_to_string(&solution));
+        assert_eq!(solution.len(), reference.len(), "level {}: shortest solution changed length", i);
+    }
+}
+
+#[test]
+fn following_hints_wins() {
+    for (i, level) in levels().iter().enumerate() {
+        let mut dice = Dice::new(level.start);
+        while !level.detect_win(&dice) {
+            let Ok(solver::Hint::Move(side)) = solver::hint(level, &dice, usize::MAX) else {
+                panic!("level {}: no hint at {:?}", i, dice.pos);
+            };
+            assert!(try_move(&mut dice, level, side).is_ok());
+        }
+    }
+}
+
+#[test]
+fn hints_count_undos_out_of_dead_ends() {
+    let mut dead_ends = 0;
+    for level in levels().iter() {
+        for side in MOVES {
+            let mut dice = Dice::new(level.start);
+            if try_move(&mut dice, level, side).is_err() || level.detect_win(&dice) {
+                continue;
+            }
+            if try_move(&mut dice, level, side).is_err() || level.detect_win(&dice) {
+                continue;
+            }
+
+            match solver::hint(level, &dice, usize::MAX).unwrap() {
+                solver::Hint::Move(_) => {}
+                solver::Hint::Undo(undos) => {
+                    dead_ends += 1;
+                    assert!(!solver::can_win(level, &dice, usize::MAX).unwrap());
+                    for _ in 0..undos - 1 {
+                        dice.undo();
+                        assert!(solver::solve_from(level, &dice).unwrap().is_none());
+                    }
+                    dice.undo();
+                    assert!(solver::can_win(level, &dice, usize::MAX).unwrap());
+                    assert!(matches!(solver::hint(level, &dice, usize::MAX), Ok(solver::Hint::Move(_))));
+                }
+                solver::Hint::Impossible => panic!("shipped levels can be won"),
+                solver::Hint::Won => panic!("hints are only asked for before winning"),
+            }
+        }
+    }
+    assert!(dead_ends > 0);
+}
+
+#[test]
+fn missing_start_points_into_the_level() {
+    let errors = Level::parse(&["name: no rows"]).err().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!((errors[0].line, errors[0].kind.clone()), (1, LevelParseErrorKind::MissingStart));
+
+    let errors = Level::parse(&["name: rows", "..1"]).err().unwrap();
+    assert_eq!(errors[0].line, 2);
+
+    let errors = parse_levels("s.1\n\nname: a\nauthor: b\n").err().unwrap();
+    assert_eq!(errors[0].line, 4);
+}
+
+#[test]
+fn large_levels_are_too_large_to_solve() {
+    let row = format!("s{}1", ".".repeat(18));
+    let rows = (0..16).map(|y| if y == 0 { row.as_str() } else { "...................." }).collect::<Vec<_>>();
+    let level = Level::parse(&rows).unwrap();
+    let dice = Dice::new(level.start);
+
+    assert_eq!(solver::solve(&level), Err(solver::SolveError::TooLarge));
+    assert_eq!(solver::can_win(&level, &dice, usize::MAX), Err(solver::SolveError::TooLarge));
+    assert_eq!(solver::hint(&level, &dice, usize::MAX), Err(solver::SolveError::TooLarge));
+    assert!(solver::analyze(&level, 2, usize::MAX).is_err());
+    // the flood fill doesn't need the search, so it still works.
+    assert!(solver::flood(&level, &dice).iter().all(|reached| *reached));
+}
+
+#[test]
+fn searches_give_up_past_their_budget() {
+    let rows = ["s.......", "........", "........", "...6....", "........", "........", "......6.", "........"];
+    let level = Level::parse(&rows).unwrap();
+    let dice = Dice::new(level.start);
+
+    assert_eq!(solver::can_win(&level, &dice, 100), Err(solver::SolveError::OutOfBudget));
+    assert_eq!(solver::hint(&level, &dice, 100), Err(solver::SolveError::OutOfBudget));
+    assert_eq!(solver::solve_within(&level, 100), Err(solver::SolveError::OutOfBudget));
+    assert!(matches!(solver::analyze(&level, 2, 100), Err(solver::SolveError::OutOfBudget)));
+}
+
+#[test]
+fn analyze_counts_solutions_and_finds_branches() {
+    // the goal takes a 3, which only DR and RRDL roll onto it with. RD comes in with a 5.
+    let level = Level::parse(&["s..", ".3."]).unwrap();
+    let analysis = solver::analyze(&level, 100, usize::MAX).unwrap();
+
+    assert_eq!(analysis.solutions, 2);
+    assert_eq!(analysis.branches.len(), 1);
+    assert_eq!(analysis.branches[0].prefix, vec![]);
+    assert_eq!(analysis.branches[0].options, vec![(Side::Right, 1), (Side::Down, 1)]);
+}
+
+#[test]
+fn rejected_moves_say_why() {
+    let level = &parse_levels("s..\n..1\n").unwrap()[0];
+    let mut dice = Dice::new(level.start);
+
+    assert_eq!(try_move(&mut dice, level, Side::Left), Err(Rejection::Void));
+    assert_eq!(try_move(&mut dice, level, Side::Right), Ok(()));
+    assert_eq!(try_move(&mut dice, level, Side::Down), Ok(()));
+    assert_eq!(try_move(&mut dice, level, Side::Right), Err(Rejection::WrongFace));
+    assert_eq!(try_move(&mut dice, level, Side::Left), Ok(()));
+    assert_eq!(try_move(&mut dice, level, Side::Up), Err(Rejection::Tail));
+    assert_eq!(try_move(&mut dice, level, Side::Down), Err(Rejection::Void));
+    assert_eq!(dice.tail.len(), 3);
+}
+
+#[test]
+fn hints_on_a_won_dice_say_so() {
+    let level = &levels()[0];
+    let mut dice = Dice::new(level.start);
+    for side in parse_moves("RRR").unwrap() {
+        try_move(&mut dice, level, side).unwrap();
+    }
+
+    assert!(level.detect_win(&dice));
+    assert_eq!(solver::hint(level, &dice, usize::MAX), Ok(solver::Hint::Won));
+}
+
+#[test]
+fn bad_par_points_at_the_value() {
+    let errors = Level::parse(&["par: x", "s.1"]).err().unwrap();
+    assert_eq!((errors[0].line, errors[0].column), (1, 6));
+    assert_eq!(errors[0].kind, LevelParseErrorKind::BadPar("x".to_string()));
+
+    let errors = Level::parse(&["par:   a", "s.1"]).err().unwrap();
+    assert_eq!(errors[0].column, 8);
+}
@@ -0,0 +1,110 @@
+use jam_gmtk_2022::*;
+use jam_gmtk_2022::replay::{Action, Event, Replay};
+
+
+#[test]
+fn replays_round_trip() {
+    let mut replay = Replay::default();
+    replay.record(Action::Move(Side::Right), 0.5);
+    replay.record(Action::Undo, 0.75);
+    replay.record(Action::Redo, 1.0);
+    replay.record(Action::Restart, 1.25);
+    replay.record(Action::Move(Side::Up), 2.0);
+
+    let text = replay.to_string();
+    assert_eq!(text, "R@0.50 Z@0.75 Y@1.00 X@1.25 U@2.00");
+    assert_eq!(Replay::parse(&text).unwrap(), replay);
+}
+
+#[test]
+fn plain_solutions_are_replays() {
+    let replay = Replay::parse("RRD LU").unwrap();
+    let actions = replay.events.iter().map(|event| event.action).collect::<Vec<_>>();
+    assert_eq!(actions, [
+        Action::Move(Side::Right), Action::Move(Side::Right), Action::Move(Side::Down),
+        Action::Move(Side::Left), Action::Move(Side::Up),
+    ]);
+    assert!(replay.events.iter().all(|event| event.time.is_none()));
+
+    // a time in a run of actions belongs to the last one.
+    let replay = Replay::parse("RR@1.5").unwrap();
+    assert_eq!(replay.events[1], Event { action: Action::Move(Side::Right), time: Some(1.5) });
+
+    assert!(Replay::parse("RQ").is_err());
+    assert!(Replay::parse("R@soon").is_err());
+}
+
+#[test]
+fn replaying_undos_and_restarts() {
+    let level = &parse_levels("......\n.s..4.\n......\n").unwrap()[0];
+    let replay = Replay::parse("R D Z X R R Z R R").unwrap();
+
+    let mut dice = Dice::new(level.start);
+    for event in replay.events.iter() {
+        assert!(event.action.apply(&mut dice, level));
+    }
+    assert!(level.detect_win(&dice));
+
+    // nothing to undo or restart at the start.
+    let mut dice = Dice::new(level.start);
+    assert!(!Action::Undo.apply(&mut dice, level));
+    assert!(!Action::Restart.apply(&mut dice, level));
+}
+
+#[test]
+fn new_moves_clear_the_redos() {
+    let level = &parse_levels("......\n.s..4.\n......\n").unwrap()[0];
+    let replay = Replay::parse("R R Z Z Y Y Z").unwrap();
+
+    let mut dice = Dice::new(level.start);
+    for event in replay.events.iter() {
+        assert!(event.action.apply(&mut dice, level));
+    }
+    assert_eq!(dice.pos, level.start + Side::Right.unit());
+    assert!(dice.can_redo());
+
+    // stepping back onto the tail is an undo too, so it can be redone.
+    assert!(try_move(&mut dice, level, Side::Left).is_ok());
+    assert!(Action::Redo.apply(&mut dice, level));
+    assert!(Action::Redo.apply(&mut dice, level));
+    assert!(!dice.can_redo());
+    assert!(try_move(&mut dice, level, Side::Right).is_ok());
+    assert!(level.detect_win(&dice));
+
+    assert!(Action::Undo.apply(&mut dice, level));
+    assert!(dice.can_redo());
+    assert!(try_move(&mut dice, level, Side::Down).is_ok());
+    assert!(!dice.can_redo());
+    assert!(!Action::Redo.apply(&mut dice, level));
+}
+
+#[test]
+fn undoing_a_restart_brings_the_dice_back() {
+    let level = &parse_levels("......\n.s..4.\n......\n").unwrap()[0];
+    let mut dice = Dice::new(level.start);
+    for side in [Side::Right, Side::Down, Side::Right] {
+        assert!(try_move(&mut dice, level, side).is_ok());
+    }
+    let (pos, orientation, tail) = (dice.pos, dice.orientation(), dice.tail.clone());
+
+    assert!(Action::Restart.apply(&mut dice, level));
+    assert_eq!(dice.pos, level.start);
+    assert!(dice.tail.is_empty());
+
+    // one undo takes back the whole restart.
+    assert!(Action::Undo.apply(&mut dice, level));
+    assert_eq!((dice.pos, dice.orientation(), &dice.tail), (pos, orientation, &tail));
+
+    assert!(Action::Redo.apply(&mut dice, level));
+    assert_eq!(dice.pos, level.start);
+
+    // moves after a restart come off first.
+    assert!(try_move(&mut dice, level, Side::Up).is_ok());
+    assert!(Action::Undo.apply(&mut dice, level));
+    assert!(Action::Undo.apply(&mut dice, level));
+    assert_eq!((dice.pos, dice.orientation(), &dice.tail), (pos, orientation, &tail));
+    for _ in 0..3 {
+        assert!(Action::Undo.apply(&mut dice, level));
+    }
+    assert!(!Action::Undo.apply(&mut dice, level));
+}
@@ -0,0 +1,43 @@
+use jam_gmtk_2022::*;
+use jam_gmtk_2022::save::Save;
+
+
+fn level(rows: &[&str]) -> Level {
+    Level::parse(rows).unwrap()
+}
+
+
+#[test]
+fn content_hash_ignores_meta_and_padding() {
+    let plain = level(&["s..1", ".."]);
+    assert_eq!(plain.content_hash(), level(&["name: a", "par: 3", "s..1", "..  "]).content_hash());
+    assert_ne!(plain.content_hash(), level(&["s..2", ".."]).content_hash());
+    assert_ne!(plain.content_hash(), level(&[".s.1", ".."]).content_hash());
+}
+
+#[test]
+fn saves_round_trip() {
+    let levels = [level(&["s..1"]), level(&["s.2"]), level(&["s3"])];
+
+    let mut save = Save::default();
+    save.complete(&levels[0], 5);
+    save.complete(&levels[0], 3);
+    save.complete(&levels[0], 4);
+    save.complete(&levels[1], 2);
+    save.set_last_level(&levels[2]);
+
+    let save = Save::parse(&save.to_string());
+    assert_eq!(save.best_moves(&levels[0]), Some(3));
+    assert_eq!(save.best_moves(&levels[1]), Some(2));
+    assert!(!save.is_completed(&levels[2]));
+    assert_eq!(save.last_level_index(&levels), Some(2));
+    assert_eq!(save.last_level_index(&levels[..2]), None);
+}
+
+#[test]
+fn damaged_saves_still_load() {
+    let save = Save::parse("best nothex 3\nlast\nbest 00000000000000ff 7\nwhat\n");
+    assert_eq!(save.best.len(), 1);
+    assert_eq!(save.best[&0xff], 7);
+    assert_eq!(save.last_level, None);
+}